@@ -1,7 +1,7 @@
 #[macro_export]
 macro_rules! info {
     ($($arg:tt)*) => {
-        eprintln!("{}: {}", env!("CARGO_PKG_NAME"), format!($($arg)*));
+        eprintln!("{}: {}", env!("CARGO_PKG_NAME"), format!($($arg)*))
     };
 }
 
@@ -1,5 +1,11 @@
-use std::path::PathBuf;
-use std::{env, io};
+use crate::link;
+use crate::prompt::{self, Prompter};
+use crate::report::{self, Reporter};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{env, fs, io};
 
 #[cfg(target_family = "unix")]
 const HOME_VAR: &str = "HOME";
@@ -7,11 +13,62 @@ const HOME_VAR: &str = "HOME";
 const HOME_VAR: &str = "USERPROFILE";
 
 const OSOY_HOME_VAR: &str = "OSOY_HOME";
+const OSOY_LINK_MODE_VAR: &str = "OSOY_LINK_MODE";
+
+/// File in Osoy home with user settings.
+pub const SETTINGS: &str = "config.toml";
+
+/// User settings read from Osoy home.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Id namespace of repositories cloned from local paths.
+    pub local: String,
+    /// Directory whose repositories are placed in local namespace by their path relative to it
+    /// instead of their absolute path.
+    pub local_root: Option<PathBuf>,
+    /// Prefixes such as `gh` in `gh:owner/repo` mapped to a domain or url prefix.
+    pub shorthands: BTreeMap<String, String>,
+    /// Tags selectable as `@tag` in targets mapped to the glob patterns they include.
+    pub tags: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            local: "local".into(),
+            local_root: None,
+            shorthands: BTreeMap::new(),
+            tags: BTreeMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// Read settings of Osoy home returning defaults if it has none.
+    pub fn read(home: &Path) -> io::Result<Self> {
+        match fs::read_to_string(home.join(SETTINGS)) {
+            Ok(content) => toml::from_str(&content).map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid '{}': {}", SETTINGS, err),
+                )
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Config {
+    pub home: PathBuf,
     pub src: PathBuf,
     pub bin: PathBuf,
+    pub trash: PathBuf,
+    pub link_mode: link::Mode,
+    pub reporter: Arc<dyn Reporter>,
+    pub prompter: Arc<dyn Prompter>,
 }
 
 pub fn home_path(rel_path: &str) -> io::Result<PathBuf> {
@@ -25,21 +82,55 @@ pub fn home_path(rel_path: &str) -> io::Result<PathBuf> {
     }
 }
 
+/// Get Osoy home given by `OSOY_HOME` defaulting to `.osoy` in home directory.
+pub fn home_from_env() -> PathBuf {
+    let env_osoy_home = env::var(OSOY_HOME_VAR).unwrap_or("".into());
+    match env_osoy_home.len() {
+        0 => home_path(".osoy").unwrap(),
+        _ => env_osoy_home.into(),
+    }
+}
+
 impl Config {
     pub fn from_env() -> Self {
-        let home = {
-            let env_osoy_home = env::var(OSOY_HOME_VAR).unwrap_or("".into());
-            match env_osoy_home.len() {
-                0 => home_path(".osoy").unwrap(),
-                _ => env_osoy_home.into(),
-            }
-        };
+        Self::from_home(&home_from_env())
+    }
 
+    /// Create configuration for Osoy home in given directory.
+    pub fn from_home(home: &Path) -> Self {
         Self {
+            home: home.into(),
             src: home.join("src"),
             bin: home.join("bin"),
+            trash: home.join("trash"),
+            link_mode: env::var(OSOY_LINK_MODE_VAR)
+                .ok()
+                .and_then(|name| name.parse().ok())
+                .unwrap_or_default(),
+            reporter: report::from_env(),
+            prompter: Arc::new(prompt::Terminal::default()),
         }
     }
+
+    /// Read settings of Osoy home.
+    pub fn settings(&self) -> io::Result<Settings> {
+        Settings::read(&self.home)
+    }
+
+    /// Replace the reporter which operators write their output to.
+    pub fn with_reporter(self, reporter: Arc<dyn Reporter>) -> Self {
+        Self { reporter, ..self }
+    }
+
+    /// Replace the mode in which files are linked.
+    pub fn with_link_mode(self, link_mode: link::Mode) -> Self {
+        Self { link_mode, ..self }
+    }
+
+    /// Replace the prompter which operators ask questions from.
+    pub fn with_prompter(self, prompter: Arc<dyn Prompter>) -> Self {
+        Self { prompter, ..self }
+    }
 }
 
 #[cfg(test)]
@@ -1,11 +1,11 @@
 use crate::config;
+use crate::prompt::Prompter;
 use git2::build::{CheckoutBuilder, RepoBuilder};
 use git2::{
-    Cred, CredentialType, Error, FetchOptions, Progress, RemoteCallbacks, Repository, Status,
+    Branch, BranchType, Cred, CredentialType, Error, ErrorClass, ErrorCode, FetchOptions, Progress,
+    PushOptions, RemoteCallbacks, Repository, Status, StatusOptions,
 };
-use std::cmp::{max, min};
 use std::collections::HashMap;
-use std::io::{stdout, Write};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Mutex, RwLock};
@@ -20,16 +20,23 @@ struct AuthCache {
 impl AuthCache {
     fn credentials(
         &mut self,
+        prompter: &dyn Prompter,
         path: &Path,
         username: Option<&str>,
         allowed_types: CredentialType,
     ) -> Result<Cred, Error> {
+        let answer = |answer: Option<String>| {
+            answer.ok_or_else(|| Error::from_str("credentials not provided"))
+        };
+
         if allowed_types.is_ssh_key() {
             let key_path = config::home_path(".ssh/id_rsa").unwrap();
             let pubkey_path = config::home_path(".ssh/id_rsa.pub").unwrap();
 
             if self.ssh_tries.get(path) == Some(&self.ssh_password) {
-                self.ssh_password = ask_secret!("password for '{}':", key_path.display());
+                self.ssh_password = answer(
+                    prompter.ask_secret(&format!("password for '{}':", key_path.display())),
+                )?;
             }
             self.ssh_tries
                 .insert(path.into(), self.ssh_password.clone());
@@ -37,7 +44,9 @@ impl AuthCache {
             Cred::ssh_key(
                 &match username {
                     Some(name) => name.into(),
-                    None => ask_string!("username for '{}':", path.display()),
+                    None => {
+                        answer(prompter.ask_string(&format!("username for '{}':", path.display())))?
+                    }
                 },
                 Some(&pubkey_path),
                 &key_path,
@@ -45,11 +54,11 @@ impl AuthCache {
             )
         } else if allowed_types.is_user_pass_plaintext() {
             Cred::userpass_plaintext(
-                &ask_string!("username for '{}':", path.display()),
-                &ask_secret!("password for '{}':", path.display()),
+                &answer(prompter.ask_string(&format!("username for '{}':", path.display())))?,
+                &answer(prompter.ask_secret(&format!("password for '{}':", path.display())))?,
             )
         } else {
-            unimplemented!()
+            Err(Error::from_str("unsupported credential type"))
         }
     }
 }
@@ -92,42 +101,6 @@ impl std::ops::Add for ProgressObjects {
     }
 }
 
-impl ProgressObjects {
-    pub fn print(&self) {
-        let stdout = stdout();
-        if termion::is_tty(&stdout) {
-            let mut stdout = stdout.lock();
-            let space = 50;
-            let (left, mid, right, part) = match self.total {
-                0 => (0, 0, space, 0),
-                _ => {
-                    let indexed = space * self.indexed / self.total;
-                    let received = space * self.received / self.total;
-                    let lesser = min(indexed, received);
-                    let greater = max(indexed, received);
-                    (
-                        lesser,
-                        greater - lesser,
-                        space - greater,
-                        100 * min(self.indexed, self.received) / self.total,
-                    )
-                }
-            };
-            write!(
-                stdout,
-                "\r[{}{}{}] {}%",
-                "=".repeat(left),
-                "-".repeat(mid),
-                "·".repeat(right),
-                part
-            )
-            .ok()
-            .map(|_| stdout.flush());
-            write!(stdout, "\r{}", termion::clear::CurrentLine).ok();
-        }
-    }
-}
-
 pub enum FetchMessage {
     Progress(ProgressObjects),
     Done(
@@ -143,6 +116,7 @@ pub enum FetchMessage {
 struct Fetch {
     limit: usize,
     auth_cache: Arc<Mutex<AuthCache>>,
+    prompter: Arc<dyn Prompter>,
     threads: Arc<RwLock<Vec<JoinHandle<()>>>>,
     queue: Arc<Mutex<Vec<Sender<()>>>>,
     progress: Arc<RwLock<HashMap<PathBuf, ProgressObjects>>>,
@@ -150,16 +124,17 @@ struct Fetch {
 }
 
 impl Fetch {
-    fn new(limit: usize) -> (Self, Receiver<FetchMessage>) {
+    fn new(limit: usize, prompter: Arc<dyn Prompter>) -> (Self, Receiver<FetchMessage>) {
         let (sender, receiver) = channel();
         (
             Self {
                 limit,
                 auth_cache: Arc::new(Mutex::new(AuthCache::default())),
+                prompter,
                 threads: Arc::new(RwLock::new(vec![])),
                 queue: Arc::new(Mutex::new(vec![])),
                 progress: Arc::new(RwLock::new(HashMap::new())),
-                sender: sender,
+                sender,
             },
             receiver,
         )
@@ -174,7 +149,7 @@ impl Fetch {
         self.auth_cache
             .lock()
             .unwrap()
-            .credentials(path, username, allowed_types)
+            .credentials(&*self.prompter, path, username, allowed_types)
     }
 
     fn progress(&self) -> ProgressObjects {
@@ -188,7 +163,7 @@ impl Fetch {
     fn update_progress(&self, path: &Path, prog: ProgressObjects) -> bool {
         match self.progress.try_write() {
             Ok(mut progress) => progress.insert(path.to_path_buf(), prog),
-            Err(_) => return false,
+            Err(_) => return true,
         };
         self.sender
             .send(FetchMessage::Progress(self.progress()))
@@ -206,10 +181,10 @@ impl Fetch {
         self.progress
             .write()
             .map(|mut progress| {
-                progress.get_mut(path).map(|mut prog| {
+                if let Some(prog) = progress.get_mut(path) {
                     prog.received = prog.total;
                     prog.indexed = prog.total;
-                })
+                }
             })
             .ok();
     }
@@ -224,7 +199,7 @@ impl Fetch {
 
     fn free_slot(&self) {
         let mut queue = self.queue.lock().unwrap();
-        if queue.len() > 0 {
+        if !queue.is_empty() {
             queue.remove(0).send(()).ok();
         }
     }
@@ -248,21 +223,58 @@ impl Fetch {
     }
 }
 
-fn fetch_options<'cb>(path: &'cb Path, fetch: Fetch) -> FetchOptions<'cb> {
+fn remote_callbacks<'cb>(path: &'cb Path, fetch: Fetch) -> RemoteCallbacks<'cb> {
     let mut callbacks = RemoteCallbacks::new();
     {
-        let path = path.clone();
         let fetch = fetch.clone();
         callbacks.credentials(move |_, username, allowed_types| {
             fetch.credentials(path, username, allowed_types)
         });
     }
-    {
-        let path = path.clone();
-        callbacks.transfer_progress(move |prog| fetch.update_progress(path, prog.into()));
-    }
+    callbacks.transfer_progress(move |prog| fetch.update_progress(path, prog.into()));
+
+    callbacks
+}
 
+fn fetch_options<'cb>(path: &'cb Path, fetch: Fetch) -> FetchOptions<'cb> {
     let mut options = FetchOptions::new();
+    options.remote_callbacks(remote_callbacks(path, fetch));
+
+    options
+}
+
+/// Get error of reference rejected by remote with given status, coded as not fast-forward if
+/// the remote has commits which are missing locally.
+fn rejection(refname: &str, status: &str) -> Error {
+    let code = match status.contains("non-fast-forward") || status.contains("fetch first") {
+        true => ErrorCode::NotFastForward,
+        false => ErrorCode::GenericError,
+    };
+    Error::new(
+        code,
+        ErrorClass::Reference,
+        format!("'{}' rejected: {}", refname, status),
+    )
+}
+
+fn push_options<'cb>(path: &'cb Path, fetch: Fetch) -> PushOptions<'cb> {
+    let mut callbacks = remote_callbacks(path, fetch.clone());
+    callbacks.push_transfer_progress(move |current, total, _| {
+        fetch.update_progress(
+            path,
+            ProgressObjects {
+                total,
+                received: current,
+                indexed: current,
+            },
+        );
+    });
+    callbacks.push_update_reference(|refname, status| match status {
+        Some(status) => Err(rejection(refname, status)),
+        None => Ok(()),
+    });
+
+    let mut options = PushOptions::new();
     options.remote_callbacks(callbacks);
 
     options
@@ -291,8 +303,11 @@ fn pull_one(path: &Path, fetch: Fetch, overwrite: bool) -> Result<(Repository, S
         if analysis.0.is_up_to_date() {
             message = "up-to-date";
         } else if analysis.0.is_fast_forward() {
+            repo.checkout_tree(
+                &repo.find_object(fetch_commit.id(), None)?,
+                Some(CheckoutBuilder::default().safe()),
+            )?;
             head.set_target(fetch_commit.id(), "pull: Fast-forward")?;
-            repo.checkout_head(Some(CheckoutBuilder::default().force()))?;
         } else if analysis.0.is_normal() {
             if overwrite {
                 head.set_target(fetch_commit.id(), "pull: Overwrite")?;
@@ -315,8 +330,13 @@ fn pull_one(path: &Path, fetch: Fetch, overwrite: bool) -> Result<(Repository, S
     Ok((repo, message.into()))
 }
 
-pub fn pull(paths: Vec<PathBuf>, threads: usize, overwrite: bool) -> Receiver<FetchMessage> {
-    let (fetch, receiver) = Fetch::new(threads);
+pub fn pull(
+    paths: Vec<PathBuf>,
+    threads: usize,
+    overwrite: bool,
+    prompter: Arc<dyn Prompter>,
+) -> Receiver<FetchMessage> {
+    let (fetch, receiver) = Fetch::new(threads, prompter);
 
     spawn(move || {
         for path in paths {
@@ -330,8 +350,148 @@ pub fn pull(paths: Vec<PathBuf>, threads: usize, overwrite: bool) -> Receiver<Fe
     receiver
 }
 
-pub fn clone(url_path_pairs: Vec<(String, PathBuf)>, threads: usize) -> Receiver<FetchMessage> {
-    let (fetch, receiver) = Fetch::new(threads);
+/// Local branch to be pushed.
+struct PushTarget {
+    branch: String,
+    remote: String,
+    /// Reference on the remote to be updated.
+    merge: String,
+    /// Count of commits ahead of upstream if upstream is known.
+    ahead: Option<usize>,
+}
+
+fn push_targets(repo: &Repository, all: bool) -> Result<Vec<PushTarget>, Error> {
+    let branches = match all {
+        true => repo
+            .branches(Some(BranchType::Local))?
+            .map(|branch| branch.map(|(branch, _)| branch))
+            .collect::<Result<Vec<_>, _>>()?,
+        false => {
+            let head = repo.head()?;
+            if !head.is_branch() {
+                return Err(Error::from_str("head is not branch"));
+            }
+            vec![Branch::wrap(head)]
+        }
+    };
+
+    let config = repo.config()?;
+    let mut targets = vec![];
+    for branch in branches {
+        let name = String::from_utf8_lossy(branch.name_bytes()?).to_string();
+        let refname = format!("refs/heads/{}", name);
+        let remote = match repo.branch_upstream_remote(&refname) {
+            Ok(remote) => String::from_utf8_lossy(&remote).to_string(),
+            Err(_) if !all => "origin".into(),
+            Err(_) => continue,
+        };
+        let merge = config
+            .get_string(&format!("branch.{}.merge", name))
+            .unwrap_or_else(|_| refname.clone());
+        let ahead = match (branch.get().target(), branch.upstream()) {
+            (Some(local), Ok(upstream)) => upstream
+                .get()
+                .target()
+                .map(|remote| {
+                    repo.graph_ahead_behind(local, remote)
+                        .map(|(ahead, _)| ahead)
+                })
+                .transpose()?,
+            _ => None,
+        };
+        targets.push(PushTarget {
+            branch: name,
+            remote,
+            merge,
+            ahead,
+        });
+    }
+
+    Ok(targets)
+}
+
+fn push_one(
+    path: &Path,
+    fetch: Fetch,
+    all: bool,
+    dry_run: bool,
+) -> Result<(Repository, String), Error> {
+    let repo = Repository::open(path)?;
+    let message;
+
+    {
+        let targets = push_targets(&repo, all)?;
+        if targets.is_empty() {
+            return Err(Error::from_str("no branches with upstream"));
+        }
+        let pending = targets
+            .iter()
+            .filter(|target| target.ahead != Some(0))
+            .collect::<Vec<_>>();
+
+        if pending.is_empty() {
+            message = "up-to-date".into();
+        } else if dry_run {
+            message = format!(
+                "would push {}",
+                pending
+                    .iter()
+                    .map(|target| format!(
+                        "{} ({})",
+                        target.branch,
+                        target
+                            .ahead
+                            .map_or("new".into(), |ahead| format!("{} ahead", ahead))
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        } else {
+            for target in pending {
+                repo.find_remote(&target.remote)?.push(
+                    &[format!("refs/heads/{}:{}", target.branch, target.merge)],
+                    Some(&mut push_options(path, fetch.clone())),
+                )?;
+                if target.ahead.is_none() {
+                    repo.find_branch(&target.branch, BranchType::Local)?
+                        .set_upstream(Some(&format!("{}/{}", target.remote, target.branch)))
+                        .ok();
+                }
+            }
+            message = "pushed".into();
+        }
+    }
+
+    Ok((repo, message))
+}
+
+pub fn push(
+    paths: Vec<PathBuf>,
+    threads: usize,
+    all: bool,
+    dry_run: bool,
+    prompter: Arc<dyn Prompter>,
+) -> Receiver<FetchMessage> {
+    let (fetch, receiver) = Fetch::new(threads, prompter);
+
+    spawn(move || {
+        for path in paths {
+            let fetch_clone = fetch.clone();
+            fetch.wait_and_spawn(path.clone(), move || {
+                push_one(&path, fetch_clone, all, dry_run)
+            });
+        }
+    });
+
+    receiver
+}
+
+pub fn clone(
+    url_path_pairs: Vec<(String, PathBuf)>,
+    threads: usize,
+    prompter: Arc<dyn Prompter>,
+) -> Receiver<FetchMessage> {
+    let (fetch, receiver) = Fetch::new(threads, prompter);
 
     spawn(move || {
         for (url, path) in url_path_pairs {
@@ -370,61 +530,258 @@ fn status_index_char(st: &Status) -> Option<char> {
     }
 }
 
+#[derive(Default)]
 pub struct RepoStatus {
     pub changes: Option<Vec<(char, String)>>,
     pub branch: Option<String>,
     pub graph: Option<(usize, usize)>,
+    pub stashes: usize,
+    /// Time of the last commit in seconds since the Unix epoch.
+    pub last_commit: Option<i64>,
 }
 
 impl RepoStatus {
     pub fn from(repo: &Repository) -> Self {
+        Self::read(repo, true)
+    }
+
+    /// Read status of repository leaving out changes in the index and working tree unless
+    /// `changes` is set as finding them means scanning the working tree.
+    pub fn read(repo: &Repository, changes: bool) -> Self {
         let head = repo.head().ok();
 
+        let last_commit = head
+            .as_ref()
+            .and_then(|head| head.peel_to_commit().ok())
+            .map(|commit| commit.time().seconds());
+
         let (graph, branch) = head.map_or((None, None), |head| {
             let branch = String::from_utf8_lossy(head.shorthand_bytes()).to_string();
             (
                 repo.find_reference(&format!("refs/remotes/origin/{}", &branch))
                     .ok()
-                    .map(|remote_ref| {
+                    .and_then(|remote_ref| {
                         repo.reference_to_annotated_commit(&remote_ref)
                             .ok()
-                            .map(|remote_commit| {
+                            .and_then(|remote_commit| {
                                 repo.graph_ahead_behind(head.target().unwrap(), remote_commit.id())
                                     .ok()
                             })
-                            .flatten()
-                    })
-                    .flatten(),
+                    }),
                 Some(branch),
             )
         });
 
-        let changes = repo.statuses(None).ok().map(|statuses| {
-            let (staged, unstaged): (Vec<_>, Vec<_>) = statuses
-                .iter()
-                .map(|entry| {
-                    let st = entry.status();
-                    let fname = String::from_utf8_lossy(entry.path_bytes());
-                    (
-                        status_index_char(&st).map(|ch| (ch, fname.to_string())),
-                        status_wt_char(&st).map(|ch| (ch, fname.to_string())),
-                    )
-                })
-                .unzip();
-            staged
-                .iter()
-                .chain(unstaged.iter())
-                .filter_map(|line| match line {
-                    Some(line) => Some(line.clone()),
-                    None => None,
-                })
-                .collect()
-        });
+        let changes = changes
+            .then(|| repo.statuses(None).ok())
+            .flatten()
+            .map(|statuses| {
+                let (staged, unstaged): (Vec<_>, Vec<_>) = statuses
+                    .iter()
+                    .map(|entry| {
+                        let st = entry.status();
+                        let fname = String::from_utf8_lossy(entry.path_bytes());
+                        (
+                            status_index_char(&st).map(|ch| (ch, fname.to_string())),
+                            status_wt_char(&st).map(|ch| (ch, fname.to_string())),
+                        )
+                    })
+                    .unzip();
+                staged
+                    .iter()
+                    .chain(unstaged.iter())
+                    .filter_map(|line| line.clone())
+                    .collect()
+            });
+
+        let stashes = repo.reflog("refs/stash").map_or(0, |reflog| reflog.len());
 
         Self {
             changes,
             branch,
             graph,
+            stashes,
+            last_commit,
         }
     }
+
+    fn count_changes(&self, pred: fn(char) -> bool) -> usize {
+        self.changes.as_ref().map_or(0, |changes| {
+            changes.iter().filter(|(ch, _)| pred(*ch)).count()
+        })
+    }
+
+    /// Count of changes added to the index.
+    pub fn staged(&self) -> usize {
+        self.count_changes(|ch| ch.is_uppercase())
+    }
+
+    /// Count of changes to tracked files not added to the index.
+    pub fn unstaged(&self) -> usize {
+        self.count_changes(|ch| ch.is_lowercase() && ch != 'n')
+    }
+
+    /// Count of files not tracked.
+    pub fn untracked(&self) -> usize {
+        self.count_changes(|ch| ch == 'n')
+    }
+
+    /// Whether there are any changes in the index or working tree.
+    pub fn is_dirty(&self) -> bool {
+        self.changes
+            .as_ref()
+            .is_some_and(|changes| !changes.is_empty())
+    }
+}
+
+/// Work in a repository which only exists locally.
+#[derive(Debug, Default)]
+pub struct RepoAudit {
+    /// Local branches without an upstream branch.
+    pub untracked_branches: Vec<String>,
+    /// Local branches with count of commits ahead of their upstream branch.
+    pub unpushed_branches: Vec<(String, usize)>,
+    /// Messages of stash entries.
+    pub stashes: Vec<String>,
+    pub untracked_files: Vec<String>,
+    /// Tags pointing to commits not reachable from any remote branch.
+    pub unpushed_tags: Vec<String>,
+}
+
+impl RepoAudit {
+    pub fn from(repo: &Repository) -> Result<Self, Error> {
+        let mut audit = Self::default();
+
+        for branch in repo.branches(Some(BranchType::Local))? {
+            let (branch, _) = branch?;
+            let name = String::from_utf8_lossy(branch.name_bytes()?).to_string();
+            match (branch.get().target(), branch.upstream()) {
+                (Some(local), Ok(upstream)) => {
+                    if let Some(remote) = upstream.get().target() {
+                        let (ahead, _) = repo.graph_ahead_behind(local, remote)?;
+                        if ahead > 0 {
+                            audit.unpushed_branches.push((name, ahead));
+                        }
+                    }
+                }
+                _ => audit.untracked_branches.push(name),
+            }
+        }
+
+        if let Ok(reflog) = repo.reflog("refs/stash") {
+            audit.stashes = reflog
+                .iter()
+                .map(|entry| entry.message().unwrap_or("").to_string())
+                .collect();
+        }
+
+        let mut options = StatusOptions::new();
+        options.include_untracked(true).include_ignored(false);
+        audit.untracked_files = repo
+            .statuses(Some(&mut options))?
+            .iter()
+            .filter(|entry| entry.status().contains(Status::WT_NEW))
+            .map(|entry| String::from_utf8_lossy(entry.path_bytes()).to_string())
+            .collect();
+
+        let remote_tips = repo
+            .references_glob("refs/remotes/*")?
+            .filter_map(|reference| reference.ok()?.peel_to_commit().ok())
+            .map(|commit| commit.id())
+            .collect::<Vec<_>>();
+        for tag in repo.references_glob("refs/tags/*")? {
+            let tag = tag?;
+            if let Ok(commit) = tag.peel_to_commit() {
+                let pushed = remote_tips.iter().any(|tip| {
+                    *tip == commit.id()
+                        || repo.graph_descendant_of(*tip, commit.id()).unwrap_or(false)
+                });
+                if !pushed {
+                    audit
+                        .unpushed_tags
+                        .push(String::from_utf8_lossy(tag.shorthand_bytes()).to_string());
+                }
+            }
+        }
+
+        Ok(audit)
+    }
+
+    /// Whether there is no local only work.
+    pub fn is_clean(&self) -> bool {
+        self.untracked_branches.is_empty()
+            && self.unpushed_branches.is_empty()
+            && self.stashes.is_empty()
+            && self.untracked_files.is_empty()
+            && self.unpushed_tags.is_empty()
+    }
+
+    /// Describe each finding on its own line.
+    pub fn describe(&self) -> Vec<String> {
+        self.untracked_branches
+            .iter()
+            .map(|name| format!("branch '{}' has no upstream", name))
+            .chain(
+                self.unpushed_branches
+                    .iter()
+                    .map(|(name, ahead)| format!("branch '{}' is {} ahead", name, ahead)),
+            )
+            .chain(
+                self.stashes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, message)| format!("stash@{{{}}}: {}", i, message)),
+            )
+            .chain(
+                self.untracked_files
+                    .iter()
+                    .map(|fname| format!("untracked '{}'", fname)),
+            )
+            .chain(
+                self.unpushed_tags
+                    .iter()
+                    .map(|name| format!("tag '{}' is not on any remote branch", name)),
+            )
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::Scripted;
+
+    #[test]
+    fn push_rejection() {
+        for status in ["non-fast-forward", "failed to update ref (fetch first)"] {
+            let err = rejection("refs/heads/main", status);
+            assert_eq!(err.code(), ErrorCode::NotFastForward);
+            assert!(err.message().contains("'refs/heads/main' rejected"));
+        }
+        assert_eq!(
+            rejection("refs/heads/main", "pre-receive hook declined").code(),
+            ErrorCode::GenericError
+        );
+    }
+
+    #[test]
+    fn credentials_from_prompter() {
+        let prompter = Scripted::new(vec!["user", "secret"]);
+        let mut cache = AuthCache::default();
+        let path = Path::new("/tmp/repo");
+
+        assert!(cache
+            .credentials(&prompter, path, None, CredentialType::USER_PASS_PLAINTEXT)
+            .is_ok());
+        assert_eq!(
+            prompter.questions(),
+            vec!["username for '/tmp/repo':", "password for '/tmp/repo':"]
+        );
+        assert!(cache
+            .credentials(&prompter, path, None, CredentialType::USER_PASS_PLAINTEXT)
+            .is_err());
+        assert!(cache
+            .credentials(&prompter, path, None, CredentialType::DEFAULT)
+            .is_err());
+    }
 }
@@ -10,7 +10,13 @@ pub mod gitutil;
 pub mod link;
 pub mod location;
 pub mod operator;
+pub mod picker;
+pub mod predicate;
+pub mod prompt;
 pub mod repo;
+pub mod report;
+pub mod time;
+pub mod trash;
 
 pub use config::Config;
 pub use exec::Exec;
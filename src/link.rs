@@ -1,24 +1,300 @@
-use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
 use std::{fs, io};
 
+/// File in repository root declaring which files to link.
+pub const MANIFEST: &str = ".osoy.toml";
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    pub link: Vec<ManifestLink>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestLink {
+    /// Path relative to the repository root.
+    pub path: PathBuf,
+    /// Name of the link which defaults to file name of the path.
+    pub name: Option<String>,
+}
+
+/// File in Osoy home recording which repository provides a conflicting link.
+pub const CHOICES: &str = "links.toml";
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Choices {
+    /// Link names mapped to ids of the repositories chosen to provide them.
+    #[serde(default)]
+    pub link: BTreeMap<String, String>,
+}
+
+impl Choices {
+    /// Read choices from Osoy home returning none if nothing is recorded.
+    pub fn read(home: &Path) -> io::Result<Self> {
+        let content = match fs::read_to_string(home.join(CHOICES)) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err),
+        };
+        toml::from_str(&content).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid '{}': {}", CHOICES, err),
+            )
+        })
+    }
+
+    /// Write choices to Osoy home.
+    pub fn write(&self, home: &Path) -> io::Result<()> {
+        let content = toml::to_string(self).map_err(io::Error::other)?;
+        fs::create_dir_all(home)?;
+        fs::write(home.join(CHOICES), content)
+    }
+}
+
+/// Get link name prefixed with name of the repository, e.g. `tool-build` for `build` of `tool.git`.
+pub fn prefixed(repo: &Path, name: &str) -> String {
+    let repo_name = repo
+        .file_name()
+        .map(|osname| osname.to_string_lossy().to_string())
+        .unwrap_or_default();
+    format!("{}-{}", repo_name.trim_end_matches(".git"), name)
+}
+
+/// Ensure that path is relative and does not leave the directory it is joined to.
+pub fn check_relative(path: &Path) -> io::Result<()> {
+    match path
+        .components()
+        .all(|comp| matches!(comp, Component::Normal(_) | Component::CurDir))
+    {
+        true => Ok(()),
+        false => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("path '{}' leaves the repository", path.display()),
+        )),
+    }
+}
+
+/// Ensure that name can be used as a file name in bin directory.
+pub fn check_name(name: &str) -> io::Result<()> {
+    match name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        true => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid link name '{}'", name),
+        )),
+        false => Ok(()),
+    }
+}
+
+impl Manifest {
+    /// Read manifest of repository returning `None` if repository has none.
+    pub fn read(dir: &Path) -> io::Result<Option<Self>> {
+        let content = match fs::read_to_string(dir.join(MANIFEST)) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let manifest: Self = toml::from_str(&content).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid '{}': {}", MANIFEST, err),
+            )
+        })?;
+        for link in &manifest.link {
+            check_relative(&link.path)?;
+            if let Some(name) = &link.name {
+                check_name(name)?;
+            }
+        }
+        Ok(Some(manifest))
+    }
+}
+
+/// File in bin directory recording links which are not symbolic links.
+pub const INDEX: &str = ".osoy-links.toml";
+
+/// How files are linked into bin directory.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Symbolic link with an absolute path.
+    #[default]
+    Absolute,
+    /// Symbolic link with a path relative to bin directory.
+    Relative,
+    /// Hard link.
+    Hard,
+    /// Copy of the file.
+    Copy,
+    /// Shell script which executes the file in its directory.
+    Wrapper,
+}
+
+impl Mode {
+    pub const NAMES: [&'static str; 5] = ["absolute", "relative", "hard", "copy", "wrapper"];
+
+    /// Whether links of this mode are recorded in the index.
+    fn indexed(self) -> bool {
+        matches!(self, Mode::Hard | Mode::Copy | Mode::Wrapper)
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "absolute" => Ok(Mode::Absolute),
+            "relative" => Ok(Mode::Relative),
+            "hard" => Ok(Mode::Hard),
+            "copy" => Ok(Mode::Copy),
+            "wrapper" => Ok(Mode::Wrapper),
+            _ => Err(format!("invalid link mode '{}'", s)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct IndexEntry {
+    /// Linked file relative to bin directory.
+    pub path: PathBuf,
+    pub mode: Mode,
+}
+
+/// Links in bin directory which cannot be told apart from regular files by themselves.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(transparent)]
+pub struct Index {
+    pub links: BTreeMap<String, IndexEntry>,
+}
+
+impl Index {
+    /// Read index of bin directory returning an empty index if there is none.
+    pub fn read(bin: &Path) -> io::Result<Self> {
+        match fs::read_to_string(bin.join(INDEX)) {
+            Ok(content) => toml::from_str(&content).map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid '{}': {}", INDEX, err),
+                )
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Write index to bin directory removing it if empty.
+    pub fn write(&self, bin: &Path) -> io::Result<()> {
+        match self.links.is_empty() {
+            true => match fs::remove_file(bin.join(INDEX)) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            },
+            false => fs::write(
+                bin.join(INDEX),
+                toml::to_string(self).map_err(io::Error::other)?,
+            ),
+        }
+    }
+
+    /// Get index entry of link.
+    pub fn get(sym: &Path) -> Option<IndexEntry> {
+        let name = sym.file_name()?.to_str()?;
+        Self::read(sym.parent()?).ok()?.links.remove(name)
+    }
+
+    /// Set or remove index entry of link.
+    fn set(sym: &Path, entry: Option<IndexEntry>) -> io::Result<()> {
+        let (bin, name) = match (sym.parent(), sym.file_name()) {
+            (Some(bin), Some(name)) => (bin, name.to_string_lossy().to_string()),
+            _ => return Ok(()),
+        };
+        let mut index = Self::read(bin)?;
+        let changed = match entry {
+            Some(entry) => index.links.insert(name, entry.clone()) != Some(entry),
+            None => index.links.remove(&name).is_some(),
+        };
+        match changed {
+            true => index.write(bin),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Normalize path lexically by removing `.` and resolving `..` components.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::CurDir => {}
+            Component::ParentDir if normal.file_name().is_some() => {
+                normal.pop();
+            }
+            comp => normal.push(comp),
+        }
+    }
+    normal
+}
+
+/// Get path of `to` relative to directory `from`, both being absolute.
+pub fn relative(from: &Path, to: &Path) -> PathBuf {
+    let from = normalize(from);
+    let to = normalize(to);
+    let common = from
+        .components()
+        .zip(to.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    from.components()
+        .skip(common)
+        .map(|_| Component::ParentDir.as_os_str())
+        .chain(to.components().skip(common).map(|comp| comp.as_os_str()))
+        .collect()
+}
+
+/// Get file which link in bin directory points to, in any mode.
+/// Only entries directly in bin are looked up from its index.
+pub fn target(bin: &Path, sym: &Path) -> Option<PathBuf> {
+    let dest = match sym.read_link() {
+        Ok(dest) => dest,
+        Err(_) if sym.parent() == Some(bin) => Index::get(sym)?.path,
+        Err(_) => return None,
+    };
+    Some(match dest.is_relative() {
+        false => dest,
+        true => normalize(&sym.parent()?.join(dest)),
+    })
+}
+
+/// Get mode of link in bin directory.
+pub fn mode(sym: &Path) -> Option<Mode> {
+    match sym.read_link() {
+        Ok(dest) if dest.is_relative() => Some(Mode::Relative),
+        Ok(_) => Some(Mode::Absolute),
+        Err(_) => Index::get(sym).map(|entry| entry.mode),
+    }
+}
+
 pub fn entries(bin: &Path) -> io::Result<impl Iterator<Item = (PathBuf, PathBuf)>> {
+    let bin = bin.to_path_buf();
     Ok(bin
         .read_dir()?
         .filter_map(Result::ok)
         .map(|entry| entry.path())
-        .filter_map(|sym| sym.read_link().map(|dest| (sym, dest)).ok()))
+        .filter_map(move |sym| target(&bin, &sym).map(|dest| (sym, dest))))
 }
 
 pub fn iterate(
     bin: &Path,
     repos: Vec<PathBuf>,
 ) -> io::Result<impl Iterator<Item = (PathBuf, PathBuf)>> {
-    Ok(bin
-        .read_dir()?
-        .filter_map(Result::ok)
-        .map(|entry| entry.path())
-        .filter_map(|sym| sym.read_link().map(|dest| (sym, dest)).ok())
-        .filter(move |(_, dest)| repos.iter().any(|repo| dest.starts_with(repo))))
+    Ok(entries(bin)?.filter(move |(_, dest)| repos.iter().any(|repo| dest.starts_with(repo))))
 }
 
 pub fn link_path(bin: &Path, exe: &Path) -> io::Result<PathBuf> {
@@ -31,35 +307,120 @@ pub fn link_path(bin: &Path, exe: &Path) -> io::Result<PathBuf> {
 }
 
 pub fn create(bin: &Path, exe: &Path) -> io::Result<PathBuf> {
-    fs::create_dir_all(bin).ok();
-    let sym = link_path(bin, exe)?;
-    {
-        #[cfg(target_family = "unix")]
-        {
-            use std::os::unix::fs::symlink;
-            symlink(exe, &sym)
+    create_as(bin, &link_path(bin, exe)?, exe)
+}
+
+/// Create symbolic link at `sym` pointing to `exe`.
+pub fn create_as(bin: &Path, sym: &Path, exe: &Path) -> io::Result<PathBuf> {
+    create_with(bin, sym, exe, Mode::Absolute)
+}
+
+/// Quote string for a POSIX shell.
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Create file at `sym` linking to `exe` without recording it in the index.
+fn place(sym: &Path, exe: &Path, mode: Mode) -> io::Result<()> {
+    match mode {
+        Mode::Absolute | Mode::Relative => {
+            let dest = match mode {
+                Mode::Relative => relative(sym.parent().unwrap_or(sym), exe),
+                _ => exe.into(),
+            };
+            #[cfg(target_family = "unix")]
+            {
+                use std::os::unix::fs::symlink;
+                symlink(dest, sym)
+            }
+            #[cfg(target_family = "windows")]
+            {
+                use std::os::windows::fs::symlink_file;
+                symlink_file(dest, sym)
+            }
         }
-        #[cfg(target_family = "windows")]
-        {
-            use std::os::windows::fs::symlink_file;
-            symlink_file(exe, &sym)
+        Mode::Hard => fs::hard_link(exe, sym),
+        Mode::Copy => fs::copy(exe, sym).map(|_| ()),
+        Mode::Wrapper => {
+            let dir = exe.parent().unwrap_or(exe);
+            fs::write(
+                sym,
+                format!(
+                    "#!/bin/sh\ncd {} || exit\nexec {} \"$@\"\n",
+                    shell_quote(&dir.to_string_lossy()),
+                    shell_quote(&exe.to_string_lossy())
+                ),
+            )?;
+            #[cfg(target_family = "unix")]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(sym, fs::Permissions::from_mode(0o755))?;
+            }
+            Ok(())
         }
     }
-    .map(|_| sym)
 }
 
-pub fn deref_rec(path: &Path) -> PathBuf {
-    match path.read_link() {
-        Ok(dest) => deref_rec(&match dest.is_relative() {
-            false => dest,
-            true => path.parent().unwrap().join(dest),
-        }),
-        Err(_) => path.into(),
+/// Create link at `sym` pointing to `exe` in given mode.
+pub fn create_with(bin: &Path, sym: &Path, exe: &Path, mode: Mode) -> io::Result<PathBuf> {
+    fs::create_dir_all(bin).ok();
+    place(sym, exe, mode)?;
+    if mode.indexed() {
+        let entry = IndexEntry {
+            path: relative(bin, exe),
+            mode,
+        };
+        if let Err(err) = Index::set(sym, Some(entry)) {
+            fs::remove_file(sym).ok();
+            return Err(err);
+        }
+    }
+    Ok(sym.into())
+}
+
+/// Remove link in bin directory along with its index entry.
+pub fn remove(sym: &Path) -> io::Result<()> {
+    fs::remove_file(sym)?;
+    Index::set(sym, None)
+}
+
+/// Point existing link at `sym` to `exe` keeping its mode by replacing it atomically.
+pub fn retarget(sym: &Path, exe: &Path) -> io::Result<()> {
+    let mode = mode(sym).unwrap_or_default();
+    let tmp = sym.with_file_name(format!(
+        ".{}.tmp",
+        sym.file_name()
+            .map(|osname| osname.to_string_lossy().to_string())
+            .unwrap_or_default()
+    ));
+    fs::remove_file(&tmp).ok();
+    place(&tmp, exe, mode)?;
+    fs::rename(&tmp, sym).inspect_err(|_| {
+        fs::remove_file(&tmp).ok();
+    })?;
+    match mode.indexed() {
+        true => Index::set(
+            sym,
+            Some(IndexEntry {
+                path: relative(sym.parent().unwrap_or(sym), exe),
+                mode,
+            }),
+        ),
+        false => Ok(()),
+    }
+}
+
+/// Follow links recursively returning the final file.
+/// Links recorded in the index of bin directory are followed as well.
+pub fn deref_rec(bin: &Path, path: &Path) -> PathBuf {
+    match target(bin, path) {
+        Some(dest) if dest != path => deref_rec(bin, &dest),
+        _ => path.into(),
     }
 }
 
 fn is_executable(path: &Path) -> bool {
-    let path = deref_rec(path);
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.into());
     path.is_file() && {
         #[cfg(target_family = "unix")]
         {
@@ -85,3 +446,87 @@ pub fn executables(dir: &Path) -> io::Result<impl Iterator<Item = PathBuf>> {
         .map(|entry| entry.path())
         .filter(|path| is_executable(path)))
 }
+
+/// Get pairs of link names and files to be linked in repository.
+/// Files declared in the manifest are used if present otherwise executables in the repository root.
+pub fn linkables(dir: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    match Manifest::read(dir)? {
+        Some(manifest) => Ok(manifest
+            .link
+            .into_iter()
+            .filter_map(|link| {
+                let path = dir.join(&link.path);
+                link.name
+                    .or_else(|| {
+                        path.file_name()
+                            .map(|osname| osname.to_string_lossy().to_string())
+                    })
+                    .map(|name| (name, path))
+            })
+            .collect()),
+        None => Ok(executables(dir)?
+            .filter_map(|exe| {
+                exe.file_name()
+                    .map(|osname| (osname.to_string_lossy().to_string(), exe.clone()))
+            })
+            .collect()),
+    }
+}
+
+/// Get executables in subdirectory of repository.
+pub fn executables_in(dir: &Path, subdir: &Path) -> io::Result<Vec<PathBuf>> {
+    check_relative(subdir)?;
+    executables(&dir.join(subdir))
+        .map(|iter| iter.collect())
+        .map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("could not read '{}': {}", subdir.display(), err),
+            )
+        })
+}
+
+/// Get files in repository matching glob pattern relative to repository root.
+pub fn glob(dir: &Path, pattern: &str) -> io::Result<Vec<PathBuf>> {
+    check_relative(Path::new(pattern))?;
+    let invalid = |err: String| io::Error::new(io::ErrorKind::InvalidInput, err);
+    let full = Path::new(&glob::Pattern::escape(&dir.to_string_lossy())).join(pattern);
+    let paths = glob::glob_with(
+        &full.to_string_lossy(),
+        glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        },
+    )
+    .map_err(|err| invalid(format!("invalid pattern '{}': {}", pattern, err)))?;
+    Ok(paths
+        .filter_map(Result::ok)
+        .filter(|path| path.is_file())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths() {
+        assert_eq!(normalize(Path::new("/a/./b/../c")), PathBuf::from("/a/c"));
+        assert_eq!(
+            relative(Path::new("/home/bin"), Path::new("/home/src/x/tool")),
+            PathBuf::from("../src/x/tool")
+        );
+        assert_eq!(
+            relative(Path::new("/home/bin"), Path::new("/home/bin/tool")),
+            PathBuf::from("tool")
+        );
+    }
+
+    #[test]
+    fn modes() {
+        for name in Mode::NAMES.iter() {
+            assert!(name.parse::<Mode>().is_ok());
+        }
+        assert!("soft".parse::<Mode>().is_err());
+    }
+}
@@ -1,7 +1,62 @@
-use crate::{Config, Exec};
-use structopt::clap::AppSettings;
+use crate::location::Matching;
+use crate::predicate::Predicate;
+use crate::repo::Selection;
+use crate::{Config, Exec, Location};
+use std::ffi::OsString;
+use std::str::FromStr;
+use structopt::clap::{self, AppSettings};
 use structopt::StructOpt;
 
+// Options matching targets against ids, shared by operators taking targets including those
+// selecting from trash. Operators flattening these define the `regex` flag themselves. Not doc
+// comments as they would replace about of the operators.
+#[derive(StructOpt, Debug, Clone, Default)]
+pub struct MatchOpt {
+    #[structopt(
+        short = "z",
+        long,
+        conflicts_with = "regex",
+        help = "Use fuzzy matching"
+    )]
+    pub fuzzy: bool,
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Leave out repositories matching glob pattern or tag"
+    )]
+    pub exclude: Vec<Location>,
+}
+
+impl MatchOpt {
+    /// Select repositories matching targets, as regular expressions if `regex` is set.
+    pub fn selection(&self, config: &Config, targets: Vec<Location>, regex: bool) -> Selection {
+        Selection::new(config, targets, Matching::new(regex, self.fuzzy))
+            .with_exclude(self.exclude.clone())
+    }
+}
+
+// Options narrowing down cloned repositories selected by targets, also by their state.
+#[derive(StructOpt, Debug, Clone, Default)]
+pub struct SelectOpt {
+    #[structopt(flatten)]
+    pub matching: MatchOpt,
+    #[structopt(
+        long = "where",
+        number_of_values = 1,
+        help = "Select only repositories satisfying predicate, e.g. dirty or last-commit>2y"
+    )]
+    pub predicates: Vec<Predicate>,
+}
+
+impl SelectOpt {
+    /// Select repositories matching targets and satisfying predicates.
+    pub fn selection(&self, config: &Config, targets: Vec<Location>, regex: bool) -> Selection {
+        self.matching
+            .selection(config, targets, regex)
+            .with_predicates(self.predicates.clone())
+    }
+}
+
 macro_rules! operator {
     ($($oper:tt),*$(,)?) => {
         $(
@@ -36,8 +91,11 @@ macro_rules! operator {
 }
 
 operator!(
+    adopt,
+    audit,
     clone,
     completions,
+    doctor,
     execute,
     link,
     list,
@@ -45,7 +103,40 @@ operator!(
     make,
     new,
     pull,
+    push,
     remove,
     rename,
+    restore,
+    shell_init,
+    status,
     unlink,
 );
+
+impl Operator {
+    /// Parse arguments like `from_iter_safe` additionally rejecting targets which are not valid
+    /// regular expressions when operator is told to use them.
+    pub fn from_iter_checked<I>(iter: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString> + Clone,
+    {
+        let matches = Self::clap().get_matches_from_safe(iter)?;
+        if let (_, Some(sub)) = matches.subcommand() {
+            if sub.is_present("regex") {
+                for name in &["target", "targets"] {
+                    for value in sub.values_of(name).into_iter().flatten() {
+                        if let Err(err) = Location::from_str(value)
+                            .and_then(|mut location| location.check_regex())
+                        {
+                            return Err(clap::Error::with_description(
+                                &format!("Invalid value for '<{}>': {}", name, err),
+                                clap::ErrorKind::ValueValidation,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(Self::from_clap(&matches))
+    }
+}
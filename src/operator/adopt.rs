@@ -0,0 +1,137 @@
+use crate::config::Settings;
+use crate::operator::link;
+use crate::{repo, Config, Exec, Location};
+use git2::Repository;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(about = "Move existing repositories into Osoy home")]
+pub struct Opt {
+    #[structopt(long, help = "Leave repositories in place and link to them instead")]
+    pub in_place: bool,
+    #[structopt(short, long, help = "Do not prompt")]
+    pub force: bool,
+    #[structopt(short, long, help = "Link executables without prompting")]
+    pub link: bool,
+    #[structopt(short, long, help = "Show detailed output")]
+    pub verbose: bool,
+    #[structopt(required = true, min_values = 1, help = "Paths of repositories")]
+    pub paths: Vec<PathBuf>,
+}
+
+impl Opt {
+    /// Get location of repository from its origin.
+    fn location(path: &Path, settings: &Settings) -> io::Result<Location> {
+        let repository = Repository::open(path).map_err(|err| io::Error::other(err.message()))?;
+        let remote = repository
+            .find_remote("origin")
+            .map_err(|_| io::Error::other("no origin found"))?;
+        let url = remote
+            .url()
+            .ok_or_else(|| io::Error::other("origin is not valid UTF-8"))?;
+        Location::parse_with(url, settings).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid origin '{}': {}", url, err),
+            )
+        })
+    }
+
+    /// Move or link repository to its place in source directory returning its location.
+    fn adopt(&self, config: &Config, path: &Path) -> io::Result<Location> {
+        let path = path.canonicalize()?;
+        let src = config
+            .src
+            .canonicalize()
+            .unwrap_or_else(|_| config.src.clone());
+        if path.starts_with(&src) {
+            return Err(io::Error::other("already in source directory"));
+        }
+        let location = Self::location(&path, &config.settings()?)?;
+        crate::link::check_relative(Path::new(&location.id())).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "origin '{}' has no place in source directory",
+                    location.url()
+                ),
+            )
+        })?;
+        let dest = config.src.join(location.id());
+        if dest.symlink_metadata().is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                match dest.canonicalize().is_ok_and(|dest| dest == path) {
+                    true => "already adopted".into(),
+                    false => format!("'{}' already exists", location.id()),
+                },
+            ));
+        }
+
+        dest.parent().map(fs::create_dir_all).transpose()?;
+        match self.in_place {
+            true => {
+                #[cfg(target_family = "unix")]
+                {
+                    std::os::unix::fs::symlink(&path, &dest)?;
+                }
+                #[cfg(target_family = "windows")]
+                {
+                    std::os::windows::fs::symlink_dir(&path, &dest)?;
+                }
+            }
+            false => repo::move_dir(&path, &dest)?,
+        }
+        Ok(location)
+    }
+}
+
+impl Exec for Opt {
+    fn exec(self, config: Config) -> i32 {
+        let mut errors = 0;
+
+        for path in &self.paths {
+            match self.adopt(&config, path) {
+                Ok(location) => {
+                    if self.verbose {
+                        config.reporter.info(&format!(
+                            "adopted '{}' as '{}'",
+                            path.display(),
+                            location.id()
+                        ));
+                    }
+                    let exes = crate::link::linkables(&config.src.join(location.id()))
+                        .map_or(0, |linkables| linkables.len());
+                    if exes > 0
+                        && (self.link
+                            || (!self.force
+                                && config.prompter.ask_bool(&format!(
+                                    "link executables of '{}'?",
+                                    location.id()
+                                ))))
+                    {
+                        errors += link::link_repo(
+                            &config,
+                            &config.src.join(location.id()),
+                            &link::Options {
+                                force: true,
+                                verbose: self.verbose,
+                                ..Default::default()
+                            },
+                        );
+                    }
+                }
+                Err(err) => {
+                    errors += 1;
+                    config
+                        .reporter
+                        .info(&format!("could not adopt '{}': {}", path.display(), err));
+                }
+            }
+        }
+
+        errors
+    }
+}
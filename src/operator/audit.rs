@@ -0,0 +1,69 @@
+use crate::gitutil::RepoAudit;
+use crate::operator::SelectOpt;
+use crate::repo;
+use crate::{Config, Exec, Location};
+use git2::Repository;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(about = "Find work which only exists locally")]
+pub struct Opt {
+    #[structopt(short, long, help = "Use regular expressions")]
+    pub regex: bool,
+    #[structopt(flatten)]
+    pub select: SelectOpt,
+    #[structopt(help = Location::about())]
+    pub targets: Vec<Location>,
+}
+
+impl Exec for Opt {
+    fn exec(self, config: Config) -> i32 {
+        let mut errors = 0;
+
+        match repo::iterate_matching_exists(
+            &config.src,
+            self.select.selection(&config, self.targets, self.regex),
+        ) {
+            Ok(iter) => {
+                let (mut total, mut unclean) = (0, 0);
+                for path in iter {
+                    total += 1;
+                    let id = path
+                        .strip_prefix(&config.src)
+                        .unwrap()
+                        .display()
+                        .to_string();
+                    match Repository::open(&path).and_then(|repo| RepoAudit::from(&repo)) {
+                        Ok(audit) => {
+                            if !audit.is_clean() {
+                                unclean += 1;
+                                config.reporter.print(
+                                    &audit
+                                        .describe()
+                                        .iter()
+                                        .fold(id, |state, line| format!("{}\n  {}", state, line)),
+                                );
+                            }
+                        }
+                        Err(err) => {
+                            errors += 1;
+                            config
+                                .reporter
+                                .info(&format!("could not audit '{}': {}", id, err));
+                        }
+                    }
+                }
+                config.reporter.info(&format!(
+                    "{} of {} repositories have local only work",
+                    unclean, total
+                ));
+            }
+            Err(err) => {
+                errors += 1;
+                config.reporter.info(&err.to_string())
+            }
+        }
+
+        errors
+    }
+}
@@ -1,5 +1,6 @@
 use crate::gitutil::{clone, FetchMessage};
 use crate::{repo, Config, Exec, Location};
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
@@ -17,13 +18,40 @@ impl Exec for Opt {
     fn exec(self, config: Config) -> i32 {
         let mut errors = 0;
 
-        let receiver = clone(
-            self.targets
-                .iter()
-                .map(|location| (location.url(), config.src.join(&location.id())))
-                .collect(),
-            self.parallel,
-        );
+        let settings = match config.settings() {
+            Ok(settings) => settings,
+            Err(err) => {
+                config.reporter.info(&err.to_string());
+                return 1;
+            }
+        };
+        let mut targets: Vec<(String, PathBuf)> = vec![];
+        for location in self.targets {
+            let display = location.to_string();
+            match location.with_settings(&settings) {
+                // Urls differing only by suffix, port or credentials share a directory which
+                // must not be cloned into, or removed if cloning fails, for another of them.
+                Ok(location) => {
+                    let path = config.src.join(location.id());
+                    if path.exists() || targets.iter().any(|(_, dest)| dest == &path) {
+                        errors += 1;
+                        config
+                            .reporter
+                            .info(&format!("'{}' already exists", location.id()));
+                    } else {
+                        targets.push((location.url(), path));
+                    }
+                }
+                Err(err) => {
+                    errors += 1;
+                    config
+                        .reporter
+                        .info(&format!("invalid location '{}': {}", display, err));
+                }
+            }
+        }
+
+        let receiver = clone(targets, self.parallel, config.prompter.clone());
         while let Ok(msg) = receiver.recv() {
             match msg {
                 FetchMessage::Done((path, res, prog)) => {
@@ -32,24 +60,21 @@ impl Exec for Opt {
                         .unwrap()
                         .display()
                         .to_string();
-                    println!(
-                        "{} {}",
-                        id,
-                        match res {
-                            Ok(_) => "done",
-                            Err(err) => {
-                                if self.verbose {
-                                    println!("{}", err);
-                                }
-                                errors += 1;
-                                repo::remove(&config.bin, &path).ok();
-                                "failed"
+                    let outcome = match res {
+                        Ok(_) => "done",
+                        Err(err) => {
+                            if self.verbose {
+                                config.reporter.info(&err.to_string());
                             }
+                            errors += 1;
+                            repo::remove(&config.bin, &path).ok();
+                            "failed"
                         }
-                    );
-                    prog.print();
+                    };
+                    config.reporter.result(&id, outcome);
+                    config.reporter.progress(prog);
                 }
-                FetchMessage::Progress(prog) => prog.print(),
+                FetchMessage::Progress(prog) => config.reporter.progress(prog),
             }
         }
 
@@ -0,0 +1,232 @@
+use crate::operator::rename;
+use crate::{link, repo, report, Config, Exec, Location};
+use git2::Repository;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(about = "Find and repair problems in bin and source directories")]
+pub struct Opt {
+    #[structopt(long, help = "Repair found problems")]
+    pub fix: bool,
+    #[structopt(short, long, help = "Do not prompt")]
+    pub force: bool,
+    #[structopt(short, long, help = "Show detailed output")]
+    pub verbose: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Class {
+    /// Symbolic link whose target does not exist.
+    Dangling,
+    /// Symbolic link pointing outside of the source directory.
+    Outside,
+    /// Entry in bin directory which is not a symbolic link.
+    File,
+    /// Repository whose origin does not match its path.
+    Origin,
+}
+
+impl Class {
+    const ALL: [Class; 4] = [Class::Dangling, Class::Outside, Class::File, Class::Origin];
+
+    fn name(self) -> &'static str {
+        match self {
+            Class::Dangling => "dangling",
+            Class::Outside => "outside",
+            Class::File => "file",
+            Class::Origin => "origin",
+        }
+    }
+
+    fn question(self, count: usize) -> String {
+        match self {
+            Class::Dangling => format!("remove {} dangling links?", count),
+            Class::Outside => format!("remove {} links pointing outside of source?", count),
+            Class::File => format!("remove {} files which are not links?", count),
+            Class::Origin => format!("move {} repositories to match their origin?", count),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Problem {
+    class: Class,
+    /// Entry in bin directory or repository.
+    path: PathBuf,
+    /// Target of link or path matching the origin of repository.
+    detail: PathBuf,
+}
+
+impl Problem {
+    fn fix(&self, bin: &Path) -> io::Result<()> {
+        match self.class {
+            Class::Dangling | Class::Outside => link::remove(&self.path),
+            Class::File => fs::remove_file(&self.path),
+            Class::Origin => repo::rename(bin, &self.path, &self.detail)
+                .and_then(|(links, _)| repo::retargeted(links))
+                .map(|_| ()),
+        }
+    }
+}
+
+/// Get problems with entries of bin directory.
+fn scan_bin(config: &Config) -> io::Result<Vec<Problem>> {
+    let mut entries = match config.bin.read_dir() {
+        Ok(iter) => iter
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .collect::<Vec<_>>(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+        Err(err) => return Err(err),
+    };
+    entries.sort();
+
+    Ok(entries
+        .into_iter()
+        .filter(|path| !path.ends_with(link::INDEX))
+        .filter_map(|path| {
+            path.symlink_metadata().ok()?;
+            let dest = link::deref_rec(&config.bin, &path);
+            let class = match link::target(&config.bin, &path).is_some() {
+                false => Class::File,
+                true if !dest.exists() => Class::Dangling,
+                true if !dest.starts_with(&config.src) => Class::Outside,
+                true => return None,
+            };
+            Some(Problem {
+                class,
+                path,
+                detail: dest,
+            })
+        })
+        .collect())
+}
+
+/// Get repositories whose origin does not match their path, except for those renamed while
+/// keeping their origin.
+fn scan_origins(config: &Config) -> io::Result<Vec<Problem>> {
+    let repos = match repo::iterate(&config.src) {
+        Ok(iter) => iter,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+    let settings = config.settings()?;
+    let kept = rename::kept_remotes(config)?;
+    let mut problems = repos
+        .filter(|path| !kept.contains(path))
+        .filter_map(|path| {
+            let repository = Repository::open(&path).ok()?;
+            let remote = repository.find_remote("origin").ok()?;
+            let location = Location::parse_with(remote.url()?, &settings).ok()?;
+            let expected = config.src.join(location.id());
+            match expected == path {
+                true => None,
+                false => Some(Problem {
+                    class: Class::Origin,
+                    path,
+                    detail: expected,
+                }),
+            }
+        })
+        .collect::<Vec<_>>();
+    problems.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(problems)
+}
+
+impl Opt {
+    /// Repair problems of one class returning count of problems left.
+    fn fix_class(&self, config: &Config, class: Class, problems: &[&Problem]) -> usize {
+        if !(self.force || config.prompter.ask_bool(&class.question(problems.len()))) {
+            return problems.len();
+        }
+        problems
+            .iter()
+            .filter(|problem| match problem.fix(&config.bin) {
+                Ok(_) => {
+                    if self.verbose {
+                        config
+                            .reporter
+                            .info(&format!("fixed '{}'", problem.path.display()));
+                    }
+                    false
+                }
+                Err(err) => {
+                    config.reporter.info(&format!(
+                        "could not fix '{}': {}",
+                        problem.path.display(),
+                        err
+                    ));
+                    true
+                }
+            })
+            .count()
+    }
+}
+
+impl Exec for Opt {
+    fn exec(self, config: Config) -> i32 {
+        let mut errors = 0;
+        let mut problems = vec![];
+
+        for scan in &[scan_bin, scan_origins] {
+            match scan(&config) {
+                Ok(found) => problems.extend(found),
+                Err(err) => {
+                    errors += 1;
+                    config.reporter.info(&err.to_string());
+                }
+            }
+        }
+
+        let relative = |path: &PathBuf| {
+            path.strip_prefix(&config.bin)
+                .or_else(|_| path.strip_prefix(&config.src))
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+        let rows = problems
+            .iter()
+            .map(|problem| {
+                vec![
+                    problem.class.name().into(),
+                    relative(&problem.path),
+                    match problem.class {
+                        Class::File => "".into(),
+                        _ => format!("-> {}", relative(&problem.detail)),
+                    },
+                ]
+            })
+            .collect::<Vec<_>>();
+        for line in report::table(&rows) {
+            config.reporter.print(&line);
+        }
+
+        let mut left = problems.len();
+        if self.fix {
+            left = 0;
+            for class in Class::ALL.iter() {
+                let of_class = problems
+                    .iter()
+                    .filter(|problem| problem.class == *class)
+                    .collect::<Vec<_>>();
+                if !of_class.is_empty() {
+                    left += self.fix_class(&config, *class, &of_class);
+                }
+            }
+            config.reporter.info(&format!(
+                "fixed {} of {} problems",
+                problems.len() - left,
+                problems.len()
+            ));
+        } else {
+            config
+                .reporter
+                .info(&format!("{} problems found", problems.len()));
+        }
+
+        errors + left as i32
+    }
+}
@@ -1,5 +1,6 @@
-use crate::{repo, Config, Exec, Location};
-use std::io::{stdout, Write};
+use crate::operator::SelectOpt;
+use crate::repo;
+use crate::{Config, Exec, Location};
 use std::process::{Command, Stdio};
 use structopt::StructOpt;
 
@@ -8,8 +9,12 @@ use structopt::StructOpt;
 pub struct Opt {
     #[structopt(short, long, help = "Use regular expressions")]
     pub regex: bool,
+    #[structopt(flatten)]
+    pub select: SelectOpt,
     #[structopt(short, long, help = "Run interactively")]
     pub interactive: bool,
+    #[structopt(short, long, help = "Pick repositories to execute in if several match")]
+    pub pick: bool,
     #[structopt(help = Location::about())]
     pub target: Location,
     #[structopt(help = "Command to execute in the repository")]
@@ -23,20 +28,30 @@ impl Exec for Opt {
         let mut errors = 0;
 
         let interactive = self.interactive;
-        let io_dest = || {
-            interactive
-                .then(|| Stdio::inherit())
-                .unwrap_or(Stdio::null())
+        let io_dest = || match interactive {
+            true => Stdio::inherit(),
+            false => Stdio::null(),
         };
 
-        match repo::iterate_matching_exists(&config.src, vec![self.target], self.regex) {
-            Ok(iter) => {
-                for path in iter {
-                    if interactive {
-                        println!("{}", path.strip_prefix(&config.src).unwrap().display());
-                    } else {
-                        print!("{}..", path.strip_prefix(&config.src).unwrap().display());
-                        stdout().flush().ok();
+        let selection = self
+            .select
+            .selection(&config, vec![self.target], self.regex);
+        let paths = match self.pick {
+            true => repo::pick(&config, selection),
+            false => repo::iterate_matching_exists(&config.src, selection)
+                .map(|iter| iter.collect::<Vec<_>>()),
+        };
+        match paths {
+            Ok(paths) => {
+                for path in paths {
+                    let id = path
+                        .strip_prefix(&config.src)
+                        .unwrap()
+                        .display()
+                        .to_string();
+                    match interactive {
+                        true => config.reporter.print(&id),
+                        false => config.reporter.start(&id),
                     }
                     let status = Command::new(&self.command)
                         .current_dir(&path)
@@ -49,26 +64,29 @@ impl Exec for Opt {
                     match status {
                         Ok(status) => {
                             let code = status.code();
-                            if code.as_ref().map_or(true, |c| *c != 0) {
+                            if code.as_ref().is_none_or(|c| *c != 0) {
                                 errors += 1;
                             }
-                            println!(
-                                "{}",
-                                status.code().map_or("NONE".into(), |c| (c != 0)
-                                    .then(|| format!("E{}", c))
-                                    .unwrap_or("OK".into()))
+                            config.reporter.result(
+                                &id,
+                                &code.map_or("NONE".into(), |c| match c {
+                                    0 => "OK".into(),
+                                    _ => format!("E{}", c),
+                                }),
                             )
                         }
                         Err(err) => {
                             errors += 1;
-                            info!("failed to execute '{}': {}", self.command, err)
+                            config
+                                .reporter
+                                .info(&format!("failed to execute '{}': {}", self.command, err))
                         }
                     }
                 }
             }
             Err(err) => {
                 errors += 1;
-                info!("{}", err)
+                config.reporter.info(&err.to_string())
             }
         }
 
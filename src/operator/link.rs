@@ -1,4 +1,7 @@
+use crate::operator::SelectOpt;
 use crate::{link, repo, Config, Exec, Location};
+use std::io;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
@@ -6,55 +9,373 @@ use structopt::StructOpt;
 pub struct Opt {
     #[structopt(short, long, help = "Use regular expressions")]
     pub regex: bool,
+    #[structopt(flatten)]
+    pub select: SelectOpt,
+    #[structopt(flatten)]
+    pub options: Options,
+    #[structopt(required = true, min_values = 1, help = Location::about())]
+    pub targets: Vec<Location>,
+}
+
+// How files of repositories are linked, not a doc comment as it would replace about of
+// operators flattening it.
+#[derive(StructOpt, Debug, Clone, Default)]
+pub struct Options {
     #[structopt(short, long, help = "Do not prompt")]
     pub force: bool,
     #[structopt(short, long, help = "Show detailed output")]
     pub verbose: bool,
-    #[structopt(required = true, min_values = 1, help = Location::about())]
-    pub targets: Vec<Location>,
+    #[structopt(
+        long = "path",
+        number_of_values = 1,
+        help = "Link executables in repository subdirectory"
+    )]
+    pub subdirs: Vec<PathBuf>,
+    #[structopt(
+        long = "glob",
+        number_of_values = 1,
+        help = "Link files matching pattern relative to repository root"
+    )]
+    pub globs: Vec<String>,
+    #[structopt(long = "as", help = "Name of the link when linking a single file")]
+    pub name: Option<String>,
+    #[structopt(
+        long,
+        help = "Replace conflicting links provided by other repositories"
+    )]
+    pub replace: bool,
+    #[structopt(
+        long,
+        conflicts_with = "replace",
+        help = "Prefix conflicting links with repository name"
+    )]
+    pub prefix: bool,
+    #[structopt(
+        long,
+        possible_values = &link::Mode::NAMES,
+        help = "How to link files, defaults to OSOY_LINK_MODE or absolute"
+    )]
+    pub mode: Option<link::Mode>,
 }
 
-impl Exec for Opt {
-    fn exec(self, config: Config) -> i32 {
-        let mut errors = 0;
-
-        match repo::iterate_matching_exists(&config.src, self.targets, self.regex) {
-            Ok(iter) => {
-                for path in iter {
-                    if let Ok(exe_iter) = link::executables(&path) {
-                        for exe in exe_iter.filter(|exe| {
-                            link::link_path(&config.bin, exe)
-                                .map(|sym| &link::deref_rec(&sym) != exe)
-                                .unwrap_or(false)
-                        }) {
-                            let exe_display = exe.strip_prefix(&config.src).unwrap().display();
-                            if self.force || ask_bool!("link '{}'?", exe_display) {
-                                match link::create(&config.bin, &exe) {
-                                    Ok(sym) => {
-                                        if self.verbose {
-                                            info!(
-                                                "'{}' -> '{}'",
-                                                sym.strip_prefix(&config.bin).unwrap().display(),
-                                                exe_display
-                                            );
-                                        }
-                                    }
-                                    Err(err) => {
-                                        errors += 1;
-                                        info!("could not link '{}': {}", path.display(), err);
-                                    }
-                                }
+impl Options {
+    /// Get pairs of link names and files to be linked in repository.
+    fn selection(&self, path: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+        let mut selection = match self.subdirs.is_empty() && self.globs.is_empty() {
+            true => link::linkables(path)?,
+            false => {
+                let mut files = vec![];
+                for subdir in &self.subdirs {
+                    files.extend(link::executables_in(path, subdir)?);
+                }
+                for pattern in &self.globs {
+                    files.extend(link::glob(path, pattern)?);
+                }
+                files.sort();
+                files.dedup();
+                files
+                    .into_iter()
+                    .filter_map(|file| {
+                        file.file_name()
+                            .map(|osname| (osname.to_string_lossy().to_string(), file.clone()))
+                    })
+                    .collect()
+            }
+        };
+
+        if let Some(name) = &self.name {
+            link::check_name(name)?;
+            match selection.len() {
+                1 => selection[0].0 = name.clone(),
+                count => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("--as requires a single file but {} were found", count),
+                    ))
+                }
+            }
+        }
+
+        Ok(selection)
+    }
+
+    /// Link file under name returning count of errors.
+    /// Existing link is replaced only if `replace` is set or it is broken.
+    fn link_one(
+        &self,
+        config: &Config,
+        name: &str,
+        candidate: &Candidate,
+        replace: bool,
+        confirm: bool,
+    ) -> i32 {
+        let sym = config.bin.join(name);
+        let exe = &candidate.exe;
+        let exe_display = exe.strip_prefix(&config.src).unwrap().display();
+        if link::deref_rec(&config.bin, &sym) == *exe {
+            return 0;
+        }
+        if !exe.is_file() {
+            config
+                .reporter
+                .info(&format!("'{}' does not exist", exe_display));
+            return 0;
+        }
+        if confirm
+            && !self.force
+            && !config
+                .prompter
+                .ask_bool(&format!("link '{}'?", exe_display))
+        {
+            return 0;
+        }
+        if let Ok(meta) = sym.symlink_metadata() {
+            if !meta.file_type().is_symlink() && link::target(&config.bin, &sym).is_none() {
+                config
+                    .reporter
+                    .info(&format!("'{}' is not a symbolic link", name));
+                return 1;
+            }
+            if replace || !link::deref_rec(&config.bin, &sym).exists() {
+                if let Err(err) = link::remove(&sym) {
+                    config
+                        .reporter
+                        .info(&format!("could not replace '{}': {}", name, err));
+                    return 1;
+                }
+            }
+        }
+        let mode = self.mode.unwrap_or(config.link_mode);
+        match link::create_with(&config.bin, &sym, exe, mode) {
+            Ok(sym) => {
+                if self.verbose {
+                    config.reporter.info(&format!(
+                        "'{}' -> '{}'",
+                        sym.strip_prefix(&config.bin).unwrap().display(),
+                        exe_display
+                    ));
+                }
+                0
+            }
+            Err(err) => {
+                config
+                    .reporter
+                    .info(&format!("could not link '{}': {}", exe.display(), err));
+                1
+            }
+        }
+    }
+
+    /// Link name provided by candidates returning count of errors.
+    /// Conflicts with other candidates or an existing link are resolved before linking.
+    fn link_name(
+        &self,
+        config: &Config,
+        choices: &mut link::Choices,
+        name: &str,
+        candidates: &[Candidate],
+    ) -> i32 {
+        let sym = config.bin.join(name);
+        let current = link::deref_rec(&config.bin, &sym);
+        let existing = match sym.symlink_metadata().is_ok()
+            && current.exists()
+            && !candidates.iter().any(|candidate| candidate.exe == current)
+        {
+            true => Some(repo::owner(&config.src, &current).map_or_else(
+                || current.display().to_string(),
+                |owner| {
+                    owner
+                        .strip_prefix(&config.src)
+                        .unwrap()
+                        .display()
+                        .to_string()
+                },
+            )),
+            false => None,
+        };
+
+        let mut providers = candidates
+            .iter()
+            .map(|candidate| candidate.id.clone())
+            .collect::<Vec<_>>();
+        providers.sort();
+        let own = existing
+            .as_ref()
+            .is_some_and(|owner| providers.contains(owner));
+        if !own {
+            providers.extend(existing);
+        }
+        if providers.len() < 2 {
+            return candidates
+                .iter()
+                .map(|candidate| self.link_one(config, name, candidate, own, true))
+                .sum();
+        }
+
+        if self.prefix {
+            return candidates
+                .iter()
+                .filter(|candidate| candidate.exe != current)
+                .map(|candidate| {
+                    let prefixed = link::prefixed(&candidate.repo, name);
+                    self.link_one(config, &prefixed, candidate, false, true)
+                })
+                .sum();
+        }
+
+        let conflict = format!("'{}' is provided by {}", name, enumerate(&providers));
+        let recorded = choices
+            .link
+            .get(name)
+            .filter(|id| providers.contains(id))
+            .cloned();
+        let (chosen, confirm) = match recorded {
+            _ if self.replace && candidates.len() == 1 => (Some(candidates[0].id.clone()), true),
+            Some(id) => (Some(id), true),
+            None if !self.force => {
+                let answer = config.prompter.ask_string(&format!(
+                    "{}, link which (1-{})?",
+                    conflict,
+                    providers.len()
+                ));
+                match answer.and_then(|answer| answer.trim().parse::<usize>().ok()) {
+                    Some(n) if n >= 1 && n <= providers.len() => {
+                        (Some(providers[n - 1].clone()), false)
+                    }
+                    _ => return 0,
+                }
+            }
+            None => (None, true),
+        };
+
+        match chosen {
+            Some(id) => {
+                choices.link.insert(name.into(), id.clone());
+                let mut errors = 0;
+                for candidate in candidates {
+                    match candidate.id == id {
+                        true => errors += self.link_one(config, name, candidate, true, confirm),
+                        false => {
+                            if self.verbose {
+                                config.reporter.info(&format!(
+                                    "skipping '{}' of '{}' in favour of '{}'",
+                                    name, candidate.id, id
+                                ));
+                            }
+                        }
+                    }
+                }
+                errors
+            }
+            None => {
+                config.reporter.info(&conflict);
+                1
+            }
+        }
+    }
+}
+
+/// File of a repository to be linked.
+struct Candidate {
+    id: String,
+    repo: PathBuf,
+    exe: PathBuf,
+}
+
+/// Join items into a list such as `a, b and c`.
+fn enumerate(items: &[String]) -> String {
+    match items.split_last() {
+        Some((last, init)) if !init.is_empty() => format!("{} and {}", init.join(", "), last),
+        Some((last, _)) => last.clone(),
+        None => String::new(),
+    }
+}
+
+/// Link files of repositories returning count of errors.
+pub fn link_repos(
+    config: &Config,
+    paths: impl IntoIterator<Item = PathBuf>,
+    options: &Options,
+) -> i32 {
+    let mut errors = 0;
+    let mut names: Vec<(String, Vec<Candidate>)> = vec![];
+
+    for path in paths {
+        let id = path
+            .strip_prefix(&config.src)
+            .unwrap()
+            .display()
+            .to_string();
+        match options.selection(&path) {
+            Ok(selection) => {
+                for (name, exe) in selection {
+                    let candidate = Candidate {
+                        id: id.clone(),
+                        repo: path.clone(),
+                        exe,
+                    };
+                    match names.iter_mut().find(|(other, _)| *other == name) {
+                        Some((_, candidates)) => {
+                            if candidates.iter().all(|other| other.id != id) {
+                                candidates.push(candidate)
                             }
                         }
+                        None => names.push((name, vec![candidate])),
                     }
                 }
             }
             Err(err) => {
                 errors += 1;
-                info!("{}", err)
+                config
+                    .reporter
+                    .info(&format!("could not link '{}': {}", id, err));
             }
         }
+    }
+
+    if !names.is_empty() {
+        let mut choices = match link::Choices::read(&config.home) {
+            Ok(choices) => choices,
+            Err(err) => {
+                errors += 1;
+                config.reporter.info(&err.to_string());
+                link::Choices::default()
+            }
+        };
+        let recorded = choices.clone();
+        for (name, candidates) in &names {
+            errors += options.link_name(config, &mut choices, name, candidates);
+        }
+        if choices != recorded {
+            if let Err(err) = choices.write(&config.home) {
+                errors += 1;
+                config
+                    .reporter
+                    .info(&format!("could not record link choices: {}", err));
+            }
+        }
+    }
 
-        errors
+    errors
+}
+
+/// Link files of a single repository returning count of errors.
+pub fn link_repo(config: &Config, path: &Path, options: &Options) -> i32 {
+    link_repos(config, Some(path.to_path_buf()), options)
+}
+
+impl Exec for Opt {
+    fn exec(self, config: Config) -> i32 {
+        match repo::iterate_matching_exists(
+            &config.src,
+            self.select
+                .selection(&config, self.targets.clone(), self.regex),
+        ) {
+            Ok(iter) => link_repos(&config, iter, &self.options),
+            Err(err) => {
+                config.reporter.info(&err.to_string());
+                1
+            }
+        }
     }
 }
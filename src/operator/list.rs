@@ -1,5 +1,7 @@
+use crate::operator::SelectOpt;
 use crate::{gitutil, link, repo, Config, Exec, Location};
 use git2::Repository;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use structopt::clap::ArgGroup;
 use structopt::StructOpt;
@@ -9,6 +11,8 @@ use structopt::StructOpt;
 pub struct Opt {
     #[structopt(short, long, help = "Use regular expressions")]
     pub regex: bool,
+    #[structopt(flatten)]
+    pub select: SelectOpt,
     #[structopt(short, long, group = "sublist", help = "List executables")]
     pub exe: bool,
     #[structopt(
@@ -26,41 +30,43 @@ pub struct Opt {
     pub targets: Vec<Location>,
 }
 
+/// List files of repository linked from bin directory along with their links.
 fn executable_listing(
     repo_path: &Path,
     bin_path: &Path,
     symlinks: &[(PathBuf, PathBuf)],
 ) -> Vec<String> {
-    link::executables(repo_path).map_or(vec![], |iter| {
-        iter.filter_map(|exe| {
-            let symbolics = symlinks
-                .clone()
-                .iter()
-                .filter_map(|(sym, dest)| {
-                    (dest == &exe).then(|| sym.strip_prefix(bin_path).unwrap().display())
-                })
-                .fold(String::new(), |state, sym| match state.is_empty() {
-                    true => format!(" <- {}", sym),
-                    false => format!("{}, {}", state, sym),
-                });
-
-            (!symbolics.is_empty()).then(|| {
-                format!(
-                    "{}{}",
-                    exe.strip_prefix(&repo_path).unwrap().display(),
-                    symbolics,
-                )
-            })
+    let mut linked = BTreeMap::<&Path, Vec<String>>::new();
+    for (sym, dest) in symlinks
+        .iter()
+        .filter(|(_, dest)| dest.starts_with(repo_path))
+    {
+        linked
+            .entry(dest)
+            .or_default()
+            .push(sym.strip_prefix(bin_path).unwrap().display().to_string());
+    }
+    linked
+        .into_iter()
+        .map(|(exe, mut syms)| {
+            syms.sort();
+            format!(
+                "{} <- {}",
+                exe.strip_prefix(repo_path).unwrap().display(),
+                syms.join(", ")
+            )
         })
         .collect()
-    })
 }
 
 impl Exec for Opt {
     fn exec(self, config: Config) -> i32 {
         let mut errors = 0;
 
-        match repo::iterate_matching_exists(&config.src, self.targets, self.regex) {
+        match repo::iterate_matching_exists(
+            &config.src,
+            self.select.selection(&config, self.targets, self.regex),
+        ) {
             Ok(iter) => {
                 let flag_exe_linked = self.exe_linked;
                 let flag_exe = self.exe || flag_exe_linked;
@@ -102,11 +108,10 @@ impl Exec for Opt {
                     if !self.only_details
                         || !exe_listing.is_empty()
                         || !git_listing.is_empty()
-                        || graph.as_ref().map_or(false, |g| g.0 * g.1 != 0)
+                        || graph.as_ref().is_some_and(|g| g.0 + g.1 != 0)
                     {
-                        println!(
-                            "{}",
-                            [
+                        config.reporter.print(
+                            &[
                                 path.strip_prefix(&config.src)
                                     .unwrap()
                                     .display()
@@ -116,14 +121,14 @@ impl Exec for Opt {
                                 exe_listing,
                                 git_listing,
                             ]
-                            .join("")
+                            .join(""),
                         );
                     }
                 }
             }
             Err(err) => {
                 errors += 1;
-                info!("{}", err)
+                config.reporter.info(&err.to_string())
             }
         }
 
@@ -1,4 +1,6 @@
-use crate::{repo, Config, Exec, Location};
+use crate::operator::SelectOpt;
+use crate::repo;
+use crate::{Config, Exec, Location};
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
@@ -6,19 +8,25 @@ use structopt::StructOpt;
 pub struct Opt {
     #[structopt(short, long, help = "Use regular expressions")]
     pub regex: bool,
+    #[structopt(flatten)]
+    pub select: SelectOpt,
     #[structopt(help = Location::about())]
     pub target: Location,
 }
 
 impl Exec for Opt {
     fn exec(self, config: Config) -> i32 {
-        match repo::unique(&config.src, self.target, self.regex) {
+        match repo::unique_or_pick(
+            &config,
+            self.select
+                .selection(&config, vec![self.target], self.regex),
+        ) {
             Ok(path) => {
-                println!("{}", path.display());
+                config.reporter.print(&path.display().to_string());
                 0
             }
             Err(err) => {
-                info!("{}", err);
+                config.reporter.info(&err.to_string());
                 1
             }
         }
@@ -15,24 +15,48 @@ impl Exec for Opt {
     fn exec(self, config: Config) -> i32 {
         let mut errors = 0;
 
+        let settings = match config.settings() {
+            Ok(settings) => settings,
+            Err(err) => {
+                config.reporter.info(&err.to_string());
+                return 1;
+            }
+        };
         for location in self.targets {
+            let display = location.to_string();
+            let location = match location.with_settings(&settings) {
+                Ok(location) => location,
+                Err(err) => {
+                    errors += 1;
+                    config
+                        .reporter
+                        .info(&format!("invalid location '{}': {}", display, err));
+                    continue;
+                }
+            };
             let path = config.src.join(location.id());
             if path.exists() {
-                info!("entity '{}' already exists", location.id())
+                config
+                    .reporter
+                    .info(&format!("entity '{}' already exists", location.id()))
             } else {
                 match Repository::init(path) {
                     Ok(repo) => {
                         if self.verbose {
-                            info!("new repository created '{}'", location.id());
+                            config
+                                .reporter
+                                .info(&format!("new repository created '{}'", location.id()));
                         }
                         if let Err(err) = repo.remote("origin", &location.url()) {
                             errors += 1;
-                            info!("could not set remote: {}", err);
+                            config
+                                .reporter
+                                .info(&format!("could not set remote: {}", err));
                         }
                     }
                     Err(err) => {
                         errors += 1;
-                        info!("could not init: {}", err)
+                        config.reporter.info(&format!("could not init: {}", err))
                     }
                 }
             }
@@ -1,5 +1,7 @@
 use crate::gitutil::{pull, FetchMessage};
-use crate::{repo, Config, Exec, Location};
+use crate::operator::SelectOpt;
+use crate::repo;
+use crate::{Config, Exec, Location};
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
@@ -9,6 +11,8 @@ pub struct Opt {
     pub parallel: usize,
     #[structopt(short, long, help = "Use regular expressions")]
     pub regex: bool,
+    #[structopt(flatten)]
+    pub select: SelectOpt,
     #[structopt(required = true, min_values = 1, help = Location::about())]
     pub targets: Vec<Location>,
     #[structopt(short, long, help = "Overwrite possible differences")]
@@ -21,9 +25,17 @@ impl Exec for Opt {
     fn exec(self, config: Config) -> i32 {
         let mut errors = 0;
 
-        match repo::iterate_matching_exists(&config.src, self.targets, self.regex) {
+        match repo::iterate_matching_exists(
+            &config.src,
+            self.select.selection(&config, self.targets, self.regex),
+        ) {
             Ok(iter) => {
-                let receiver = pull(iter.collect(), self.parallel, self.force);
+                let receiver = pull(
+                    iter.collect(),
+                    self.parallel,
+                    self.force,
+                    config.prompter.clone(),
+                );
                 while let Ok(msg) = receiver.recv() {
                     match msg {
                         FetchMessage::Done((path, res, prog)) => {
@@ -32,29 +44,26 @@ impl Exec for Opt {
                                 .unwrap()
                                 .display()
                                 .to_string();
-                            println!(
-                                "{} {}",
-                                id,
-                                match res {
-                                    Ok((_, msg)) => msg,
-                                    Err(err) => {
-                                        if self.verbose {
-                                            println!("{}", err);
-                                        }
-                                        errors += 1;
-                                        "failed".into()
+                            let outcome = match res {
+                                Ok((_, msg)) => msg,
+                                Err(err) => {
+                                    if self.verbose {
+                                        config.reporter.info(&err.to_string());
                                     }
+                                    errors += 1;
+                                    "failed".into()
                                 }
-                            );
-                            prog.print();
+                            };
+                            config.reporter.result(&id, &outcome);
+                            config.reporter.progress(prog);
                         }
-                        FetchMessage::Progress(prog) => prog.print(),
+                        FetchMessage::Progress(prog) => config.reporter.progress(prog),
                     }
                 }
             }
             Err(err) => {
                 errors += 1;
-                info!("{}", err)
+                config.reporter.info(&err.to_string())
             }
         }
 
@@ -0,0 +1,81 @@
+use crate::gitutil::{push, FetchMessage};
+use crate::operator::SelectOpt;
+use crate::repo;
+use crate::{Config, Exec, Location};
+use git2::ErrorCode;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(about = "Push to repository remotes")]
+pub struct Opt {
+    #[structopt(short, long, default_value = "10", help = "Count of parallel jobs")]
+    pub parallel: usize,
+    #[structopt(short, long, help = "Use regular expressions")]
+    pub regex: bool,
+    #[structopt(flatten)]
+    pub select: SelectOpt,
+    #[structopt(short, long, help = "Push all branches with upstreams")]
+    pub all: bool,
+    #[structopt(short = "n", long, help = "Show what would be pushed")]
+    pub dry_run: bool,
+    #[structopt(short, long, help = "Show detailed output")]
+    pub verbose: bool,
+    #[structopt(help = Location::about())]
+    pub targets: Vec<Location>,
+}
+
+impl Exec for Opt {
+    fn exec(self, config: Config) -> i32 {
+        let mut errors = 0;
+
+        match repo::iterate_matching_exists(
+            &config.src,
+            self.select.selection(&config, self.targets, self.regex),
+        ) {
+            Ok(iter) => {
+                let receiver = push(
+                    iter.collect(),
+                    self.parallel,
+                    self.all,
+                    self.dry_run,
+                    config.prompter.clone(),
+                );
+                while let Ok(msg) = receiver.recv() {
+                    match msg {
+                        FetchMessage::Done((path, res, prog)) => {
+                            let id = path
+                                .strip_prefix(&config.src)
+                                .unwrap()
+                                .display()
+                                .to_string();
+                            let outcome = match res {
+                                Ok((_, msg)) => msg,
+                                Err(err) => {
+                                    if self.verbose {
+                                        config.reporter.info(&err.to_string());
+                                    }
+                                    errors += 1;
+                                    match err.code() {
+                                        ErrorCode::NotFastForward => {
+                                            "rejected non-fast-forward".into()
+                                        }
+                                        _ => "failed".into(),
+                                    }
+                                }
+                            };
+                            config.reporter.result(&id, &outcome);
+                            config.reporter.progress(prog);
+                        }
+                        FetchMessage::Progress(prog) => config.reporter.progress(prog),
+                    }
+                }
+            }
+            Err(err) => {
+                errors += 1;
+                config.reporter.info(&err.to_string())
+            }
+        }
+
+        errors
+    }
+}
@@ -1,15 +1,23 @@
-use crate::{repo, Config, Exec, Location};
+use crate::operator::SelectOpt;
+use crate::repo;
+use crate::{trash, Config, Exec, Location};
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
-#[structopt(alias = "rm", about = "Remove repositories")]
+#[structopt(alias = "rm", about = "Move repositories to trash")]
 pub struct Opt {
     #[structopt(short, long, help = "Use regular expressions")]
     pub regex: bool,
+    #[structopt(flatten)]
+    pub select: SelectOpt,
     #[structopt(short, long, help = "Do not prompt")]
     pub force: bool,
     #[structopt(short, long, help = "Show detailed output")]
     pub verbose: bool,
+    #[structopt(long, help = "Delete permanently instead of moving to trash")]
+    pub purge: bool,
+    #[structopt(long, help = "Remove even if work only exists locally")]
+    pub discard_unpushed: bool,
     #[structopt(required = true, min_values = 1, help = Location::about())]
     pub targets: Vec<Location>,
 }
@@ -18,20 +26,60 @@ impl Exec for Opt {
     fn exec(self, config: Config) -> i32 {
         let mut errors = 0;
 
-        match repo::iterate_matching_exists(&config.src, self.targets, self.regex) {
+        match repo::iterate_matching_exists(
+            &config.src,
+            self.select.selection(&config, self.targets, self.regex),
+        ) {
             Ok(iter) => {
                 for path in iter {
                     let path_display = path.strip_prefix(&config.src).unwrap().display();
-                    if self.force || ask_bool!("remove '{}'?", path_display) {
-                        match repo::remove(&config.bin, &path) {
+                    let risks = repo::removal_risks(&path)
+                        .unwrap_or_else(|err| vec![format!("could not inspect: {}", err)]);
+                    if !risks.is_empty() {
+                        let message = risks.iter().fold(
+                            format!("'{}' has local only work", path_display),
+                            |state, line| format!("{}\n  {}", state, line),
+                        );
+                        match self.discard_unpushed {
+                            true => config.reporter.info(&message),
+                            false => {
+                                errors += 1;
+                                config.reporter.info(&format!(
+                                    "{}\nrefusing to remove without --discard-unpushed",
+                                    message
+                                ));
+                                continue;
+                            }
+                        }
+                    }
+                    if self.force
+                        || config.prompter.ask_bool(&format!(
+                            "{} '{}'?",
+                            match self.purge {
+                                true => "purge",
+                                false => "remove",
+                            },
+                            path_display
+                        ))
+                    {
+                        let res = match self.purge {
+                            true => repo::remove(&config.bin, &path).map(|_| ()),
+                            false => trash::put(&config.trash, &config.src, &config.bin, &path)
+                                .map(|_| ()),
+                        };
+                        match res {
                             Ok(_) => {
                                 if self.verbose {
-                                    info!("removed '{}'", path_display);
+                                    config.reporter.info(&format!("removed '{}'", path_display));
                                 }
                             }
                             Err(err) => {
                                 errors += 1;
-                                info!("could not remove '{}': {}", path.display(), err)
+                                config.reporter.info(&format!(
+                                    "could not remove '{}': {}",
+                                    path.display(),
+                                    err
+                                ))
                             }
                         }
                     }
@@ -39,7 +87,7 @@ impl Exec for Opt {
             }
             Err(err) => {
                 errors += 1;
-                info!("{}", err)
+                config.reporter.info(&err.to_string())
             }
         }
 
@@ -1,72 +1,402 @@
-use crate::{repo, Config, Exec, Location};
+use crate::operator::SelectOpt;
+use crate::repo;
+use crate::{time, Config, Exec, Location};
 use git2::Repository;
-use std::io;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
 use structopt::StructOpt;
 
+/// File in Osoy home recording renames so that they can be undone.
+const HISTORY: &str = "renames.toml";
+
 #[derive(StructOpt, Debug)]
 #[structopt(aliases = &["mv", "move"], about = "Relocate repository")]
 pub struct Opt {
-    #[structopt(short, long, help = "Use regular expressions")]
+    #[structopt(
+        short,
+        long,
+        help = "Use regular expressions, destination may refer to groups as $1"
+    )]
     pub regex: bool,
+    #[structopt(flatten)]
+    pub select: SelectOpt,
     #[structopt(short, long, help = "Show detailed output")]
     pub verbose: bool,
-    #[structopt(help = Location::about())]
-    pub target: Location,
-    #[structopt(help = Location::about())]
-    pub destination: Location,
+    #[structopt(short = "n", long, help = "Only show planned moves")]
+    pub dry_run: bool,
+    #[structopt(long, help = "Do not change origin")]
+    pub keep_remote: bool,
+    #[structopt(
+        long,
+        conflicts_with = "keep-remote",
+        help = "Change origin without moving repository"
+    )]
+    pub remote_only: bool,
+    #[structopt(
+        long,
+        conflicts_with_all = &["keep-remote", "remote-only", "regex"],
+        help = "Revert the last rename"
+    )]
+    pub undo: bool,
+    #[structopt(required_unless = "undo", help = Location::about())]
+    pub target: Option<Location>,
+    #[structopt(required_unless = "undo", help = Location::about())]
+    pub destination: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct History {
+    #[serde(default)]
+    rename: Vec<Batch>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Batch {
+    time: i64,
+    moves: Vec<Record>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Record {
+    from: String,
+    to: String,
+    /// Origin before the rename if it was changed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    origin: Option<String>,
+    /// Whether origin was deliberately left pointing elsewhere than the new location.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    keep_remote: bool,
+}
+
+impl History {
+    fn read(home: &Path) -> io::Result<Self> {
+        match fs::read_to_string(home.join(HISTORY)) {
+            Ok(content) => toml::from_str(&content).map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid '{}': {}", HISTORY, err),
+                )
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Get ids where repositories were last moved to with `--keep-remote`.
+    fn kept_remotes(&self) -> HashSet<&str> {
+        let mut kept = HashSet::new();
+        for record in self.rename.iter().flat_map(|batch| &batch.moves) {
+            kept.remove(record.from.as_str());
+            match record.keep_remote {
+                true => kept.insert(record.to.as_str()),
+                false => kept.remove(record.to.as_str()),
+            };
+        }
+        kept
+    }
+
+    fn write(&self, home: &Path) -> io::Result<()> {
+        fs::create_dir_all(home)?;
+        fs::write(
+            home.join(HISTORY),
+            toml::to_string(self).map_err(io::Error::other)?,
+        )
+    }
+}
+
+/// Get paths of repositories whose origin was kept when they were last renamed, which are thus
+/// expected to not match their location.
+pub fn kept_remotes(config: &Config) -> io::Result<Vec<PathBuf>> {
+    Ok(History::read(&config.home)?
+        .kept_remotes()
+        .into_iter()
+        .map(|id| config.src.join(id))
+        .collect())
+}
+
+/// Planned relocation of a repository.
+struct Move {
+    from: PathBuf,
+    to: PathBuf,
+    /// Origin to be set.
+    url: Option<String>,
+}
+
+/// Replace `$N` and `${N}` in template with captured groups counting from 1.
+fn substitute(template: &str, groups: &[String]) -> String {
+    lazy_static! {
+        static ref RE_GROUP: Regex = Regex::new(r"\$(?:(\d+)|\{(\d+)\})").unwrap();
+    }
+    RE_GROUP
+        .replace_all(template, |caps: &Captures| {
+            caps.get(1)
+                .or_else(|| caps.get(2))
+                .and_then(|num| num.as_str().parse::<usize>().ok())
+                .and_then(|num| num.checked_sub(1))
+                .and_then(|i| groups.get(i))
+                .cloned()
+                .unwrap_or_default()
+        })
+        .to_string()
+}
+
+fn origin(path: &Path) -> Option<String> {
+    Repository::open(path)
+        .ok()?
+        .find_remote("origin")
+        .ok()?
+        .url()
+        .map(String::from)
+}
+
+impl Opt {
+    /// Plan moves of repositories matching target.
+    fn plan(&self, config: &Config) -> io::Result<Vec<Move>> {
+        let settings = config.settings()?;
+        let target = self.target.clone().unwrap();
+        let display = target.to_string();
+        let mut target = target.with_settings(&settings).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid location '{}': {}", display, err),
+            )
+        })?;
+        let destination = self.destination.as_deref().unwrap();
+        let selection = |regex| self.select.selection(config, vec![target.clone()], regex);
+        let pairs = match self.regex {
+            true => repo::iterate_matching_exists(&config.src, selection(true))?
+                .map(|path| {
+                    let groups = target
+                        .captures(path.strip_prefix(&config.src).unwrap_or(&path))
+                        .unwrap_or_default();
+                    (path, substitute(destination, &groups))
+                })
+                .collect::<Vec<_>>(),
+            false => vec![(
+                repo::unique_or_confirm(config, selection(false))?,
+                destination.to_string(),
+            )],
+        };
+
+        pairs
+            .into_iter()
+            .map(|(path, dest)| {
+                let location = Location::parse_with(&dest, &settings).map_err(|err| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("invalid location '{}': {}", dest, err),
+                    )
+                })?;
+                Ok(Move {
+                    to: match self.remote_only {
+                        true => path.clone(),
+                        false => config.src.join(location.id()),
+                    },
+                    from: path,
+                    url: (!self.keep_remote).then(|| location.url()),
+                })
+            })
+            .collect()
+    }
+
+    /// Plan moves reverting the last rename.
+    fn plan_undo(&self, config: &Config, history: &History) -> io::Result<Vec<Move>> {
+        match history.rename.last() {
+            Some(batch) => Ok(batch
+                .moves
+                .iter()
+                .rev()
+                .map(|record| Move {
+                    from: config.src.join(&record.to),
+                    to: config.src.join(&record.from),
+                    url: record.origin.clone(),
+                })
+                .collect()),
+            None => Err(io::Error::other("nothing to undo")),
+        }
+    }
+
+    /// Ensure that moves do not collide with each other or existing repositories.
+    fn check(&self, config: &Config, moves: &[Move]) -> Vec<String> {
+        let mut problems = vec![];
+        let mut seen = HashSet::new();
+        for mv in moves {
+            let id = mv.to.strip_prefix(&config.src).unwrap_or(&mv.to).display();
+            if !seen.insert(&mv.to) {
+                problems.push(format!("multiple repositories would be moved to '{}'", id));
+            } else if mv.to != mv.from && mv.to.exists() {
+                problems.push(format!("'{}' already exists", id));
+            }
+        }
+        problems
+    }
+
+    /// Move repository and set its origin returning the record of what was done and count of
+    /// links which could not be updated.
+    fn apply(&self, config: &Config, mv: &Move) -> io::Result<(Record, usize)> {
+        let id = |path: &Path| {
+            path.strip_prefix(&config.src)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+        let previous = mv.url.as_ref().and_then(|_| origin(&mv.from));
+        let mut failed = 0;
+        if mv.to != mv.from {
+            let (links, _) = repo::rename(&config.bin, &mv.from, &mv.to)
+                .map_err(|err| io::Error::new(err.kind(), format!("rename failed: {}", err)))?;
+            if self.verbose {
+                config
+                    .reporter
+                    .info(&format!("renamed '{}' to '{}'", id(&mv.from), id(&mv.to)));
+            }
+            let mut updated = 0;
+            for (sym, res) in links {
+                match res {
+                    Ok(_) => updated += 1,
+                    Err(err) => {
+                        failed += 1;
+                        config.reporter.info(&format!(
+                            "could not update link '{}': {}",
+                            sym.strip_prefix(&config.bin).unwrap_or(&sym).display(),
+                            err
+                        ));
+                    }
+                }
+            }
+            if updated > 0 {
+                config.reporter.info(&format!("updated {} links", updated));
+            }
+        }
+        if let Some(url) = &mv.url {
+            Repository::open(&mv.to)
+                .and_then(|repo| repo.remote_set_url("origin", url))
+                .map_err(|err| io::Error::other(format!("could not set remote: {}", err)))?;
+            config.reporter.info(&format!("origin: {}", url));
+        }
+        Ok((
+            Record {
+                from: id(&mv.from),
+                to: id(&mv.to),
+                origin: previous,
+                keep_remote: self.keep_remote,
+            },
+            failed,
+        ))
+    }
 }
 
 impl Exec for Opt {
     fn exec(self, config: Config) -> i32 {
         let mut errors = 0;
-        let Self {
-            regex,
-            verbose,
-            mut target,
-            destination,
-        } = self;
-
-        match repo::unique(&config.src, target.clone(), regex) {
-            Ok(path) => {
-                let dest_path = config.src.join(destination.id());
-                let rename_res = repo::rename(&path, &dest_path);
-                if rename_res
-                    .as_ref()
-                    .err()
-                    .map(|err| {
-                        err.kind() == io::ErrorKind::AlreadyExists
-                            && match regex {
-                                true => target.matches_re(&path),
-                                false => target.matches(&path),
-                            }
-                    })
-                    .unwrap_or(true)
-                {
-                    if let Err(err) = Repository::open(&dest_path).map(|repo| {
-                        repo.remote_set_url("origin", &destination.url())
-                            .and_then(|_| Ok(info!("origin: {}", &destination.url())))
-                    }) {
-                        errors += 1;
-                        info!("could not set remote: {}", err);
-                    }
-                    if rename_res.is_ok() && verbose {
-                        info!(
-                            "renamed '{}' to '{}'",
-                            path.strip_prefix(config.src).unwrap().display(),
-                            destination.id()
-                        );
-                    }
-                } else {
+
+        let mut history = match History::read(&config.home) {
+            Ok(history) => history,
+            Err(err) => {
+                config.reporter.info(&err.to_string());
+                return 1;
+            }
+        };
+        let moves = match self.undo {
+            true => self.plan_undo(&config, &history),
+            false => self.plan(&config),
+        };
+        let moves = match moves {
+            Ok(moves) => moves,
+            Err(err) => {
+                config.reporter.info(&err.to_string());
+                return 1;
+            }
+        };
+
+        let problems = self.check(&config, &moves);
+        if !problems.is_empty() {
+            for problem in &problems {
+                config.reporter.info(problem);
+            }
+            return problems.len() as i32;
+        }
+
+        if self.dry_run {
+            for mv in &moves {
+                config.reporter.print(&format!(
+                    "{} -> {}{}",
+                    mv.from.strip_prefix(&config.src).unwrap().display(),
+                    mv.to.strip_prefix(&config.src).unwrap().display(),
+                    mv.url
+                        .as_ref()
+                        .map_or("".into(), |url| format!(" (origin {})", url))
+                ));
+            }
+            return 0;
+        }
+
+        let mut records = vec![];
+        for mv in &moves {
+            match self.apply(&config, mv) {
+                Ok((record, failed)) => {
+                    errors += failed as i32;
+                    records.push(record);
+                }
+                Err(err) => {
                     errors += 1;
-                    info!("rename failed: {}", rename_res.err().unwrap())
+                    config.reporter.info(&err.to_string());
                 }
             }
-            Err(err) => {
+        }
+
+        let changed = match self.undo {
+            true => {
+                // Keep moves which could not be reverted so that undo can be retried.
+                let remaining = history.rename.last_mut().map_or(0, |batch| {
+                    batch.moves.retain(|mv| {
+                        !records
+                            .iter()
+                            .any(|record| record.from == mv.to && record.to == mv.from)
+                    });
+                    batch.moves.len()
+                });
+                if remaining == 0 {
+                    history.rename.pop();
+                }
+                !records.is_empty()
+            }
+            false => {
+                let moved = !records.is_empty();
+                if moved {
+                    history.rename.push(Batch {
+                        time: time::now(),
+                        moves: records,
+                    });
+                }
+                moved
+            }
+        };
+        if changed {
+            if let Err(err) = history.write(&config.home) {
                 errors += 1;
-                info!("{}", err)
+                config
+                    .reporter
+                    .info(&format!("could not record rename: {}", err));
             }
         }
 
         errors
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitution() {
+        let groups = vec!["a".into(), "b".into()];
+        assert_eq!(substitute("x/$2/${1}", &groups), "x/b/a");
+        assert_eq!(substitute("x/$3", &groups), "x/");
+        assert_eq!(substitute("plain", &groups), "plain");
+    }
+}
@@ -0,0 +1,136 @@
+use crate::operator::MatchOpt;
+use crate::time::{self, format_age, parse_age};
+use crate::{report, trash, Config, Exec, Location};
+use std::collections::HashSet;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(about = "Restore repositories from trash or list its contents")]
+pub struct Opt {
+    #[structopt(short, long, help = "Use regular expressions")]
+    pub regex: bool,
+    #[structopt(flatten)]
+    pub matching: MatchOpt,
+    #[structopt(short, long, help = "Do not prompt")]
+    pub force: bool,
+    #[structopt(short, long, help = "Show detailed output")]
+    pub verbose: bool,
+    #[structopt(long, help = "Delete repositories in trash permanently")]
+    pub purge: bool,
+    #[structopt(
+        long,
+        requires = "purge",
+        parse(try_from_str = parse_age),
+        help = "Purge only repositories removed longer ago than age, e.g. 30d"
+    )]
+    pub older_than: Option<i64>,
+    #[structopt(help = Location::about())]
+    pub targets: Vec<Location>,
+}
+
+impl Exec for Opt {
+    fn exec(self, config: Config) -> i32 {
+        let mut errors = 0;
+
+        let mut matcher = match self
+            .matching
+            .selection(&config, self.targets.clone(), self.regex)
+            .matcher()
+        {
+            Ok(matcher) => matcher,
+            Err(err) => {
+                config.reporter.info(&err.to_string());
+                return 1;
+            }
+        };
+        let entries = match trash::list(&config.trash) {
+            Ok(entries) => entries,
+            Err(err) => {
+                config.reporter.info(&err.to_string());
+                return 1;
+            }
+        };
+        let now = time::now();
+        let older_than = self.older_than;
+        let mut entries = entries
+            .into_iter()
+            .filter(|entry| older_than.is_none_or(|age| now - entry.meta.time > age))
+            .filter(|entry| matcher.matches(&entry.meta.path))
+            .collect::<Vec<_>>();
+
+        if self.purge {
+            if entries.is_empty() {
+                config.reporter.info("nothing to purge");
+            } else if self.force
+                || config
+                    .prompter
+                    .ask_bool(&format!("purge {} repositories?", entries.len()))
+            {
+                for entry in entries {
+                    let id = entry.meta.path.display().to_string();
+                    match entry.purge() {
+                        Ok(_) => {
+                            if self.verbose {
+                                config.reporter.info(&format!("purged '{}'", id));
+                            }
+                        }
+                        Err(err) => {
+                            errors += 1;
+                            config
+                                .reporter
+                                .info(&format!("could not purge '{}': {}", id, err));
+                        }
+                    }
+                }
+            }
+        } else if self.targets.is_empty() {
+            let rows = entries
+                .iter()
+                .rev()
+                .map(|entry| {
+                    vec![
+                        entry.meta.path.display().to_string(),
+                        format_age(now - entry.meta.time),
+                        match entry.meta.links.len() {
+                            0 => "".into(),
+                            count => format!("{} links", count),
+                        },
+                    ]
+                })
+                .collect::<Vec<_>>();
+            for line in report::table(&rows) {
+                config.reporter.print(&line);
+            }
+        } else if entries.is_empty() {
+            errors += 1;
+            config.reporter.info("no matching entities found in trash");
+        } else {
+            // Restore only the newest removal of each repository.
+            let mut seen = HashSet::new();
+            entries.reverse();
+            entries.retain(|entry| seen.insert(entry.meta.path.clone()));
+            for entry in entries {
+                let id = entry.meta.path.display().to_string();
+                if self.force || config.prompter.ask_bool(&format!("restore '{}'?", id)) {
+                    match entry.restore(&config.src, &config.bin) {
+                        Ok(links) => {
+                            if self.verbose {
+                                config
+                                    .reporter
+                                    .info(&format!("restored '{}' with {} links", id, links));
+                            }
+                        }
+                        Err(err) => {
+                            errors += 1;
+                            config
+                                .reporter
+                                .info(&format!("could not restore '{}': {}", id, err));
+                        }
+                    }
+                }
+            }
+        }
+
+        errors
+    }
+}
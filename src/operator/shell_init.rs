@@ -0,0 +1,200 @@
+use super::Operator;
+use crate::link::shell_quote;
+use crate::{Config, Exec};
+use std::str::FromStr;
+use structopt::{clap, StructOpt};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub const NAMES: [&'static str; 3] = ["bash", "zsh", "fish"];
+}
+
+impl FromStr for Shell {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(format!("unsupported shell '{}'", s)),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(about = "Print shell functions for changing into repositories")]
+pub struct Opt {
+    #[structopt(possible_values = &Shell::NAMES)]
+    pub shell: Shell,
+    #[structopt(
+        long,
+        default_value = "oc",
+        help = "Name of function changing into repository"
+    )]
+    pub cmd: String,
+}
+
+/// Functions for POSIX like shells, shared by bash and zsh.
+const POSIX: &str = r#"{cmd}() {
+    local dir
+    dir="$(OSOY_OUTPUT=plain command {osoy} locate "$@")" && cd -- "$dir"
+}
+
+_osoy_ids() {
+    OSOY_OUTPUT=plain command {osoy} list 2>/dev/null
+}
+
+osoy_prompt() {
+    local src={src} dir="$PWD"
+    case "$dir/" in
+        "$src"/*) ;;
+        *) return 1 ;;
+    esac
+    while [ "$dir" != "$src" ]; do
+        if [ -e "$dir/.git" ]; then
+            printf '%s\n' "${dir#"$src"/}"
+            return 0
+        fi
+        dir="${dir%/*}"
+    done
+    return 1
+}
+"#;
+
+const BASH: &str = r#"
+{completions}
+_{cmd}_complete() {
+    COMPREPLY=($(compgen -W "$(_osoy_ids)" -- "${COMP_WORDS[COMP_CWORD]}"))
+}
+
+# Complete ids where the generated completion only offers flags and names of arguments.
+_osoy_complete() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" word
+    _osoy "$@"
+    if [ "$COMP_CWORD" -gt 1 ] && [[ "$cur" != -* ]]; then
+        for word in "${COMPREPLY[@]}"; do
+            [[ "$word" == [-\<]* ]] || return 0
+        done
+        COMPREPLY=($(compgen -W "$(_osoy_ids)" -- "$cur"))
+    fi
+}
+
+complete -F _{cmd}_complete {cmd}
+complete -F _osoy_complete -o bashdefault -o default {osoy}
+"#;
+
+const ZSH: &str = r#"
+{completions}
+_osoy_id_candidates() {
+    compadd -- ${(f)"$(_osoy_ids)"}
+}
+
+if (( $+functions[compdef] )); then
+    compdef _osoy_id_candidates {cmd}
+    compdef _osoy {osoy}
+fi
+"#;
+
+const FISH: &str = r#"function {cmd} --description 'Change directory into repository'
+    set -l dir (env OSOY_OUTPUT=plain {osoy} locate $argv)
+    or return
+    cd $dir
+end
+
+function __osoy_ids
+    env OSOY_OUTPUT=plain {osoy} list 2>/dev/null
+end
+
+function osoy_prompt --description 'Print id of repository in current directory'
+    set -l src {src}
+    set -l dir $PWD
+    test (string sub -l (string length -- "$src/") -- "$dir/") = "$src/"
+    or return 1
+    while test "$dir" != "$src"
+        if test -e "$dir/.git"
+            string sub -s (math (string length -- "$src/") + 1) -- $dir
+            return 0
+        end
+        set dir (string replace -r '/[^/]*$' '' -- $dir)
+    end
+    return 1
+end
+
+{completions}
+complete -c {cmd} -f -a '(__osoy_ids)'
+complete -c {osoy} -f -n 'not __fish_use_subcommand' -a '(__osoy_ids)'
+"#;
+
+/// Quote string for fish where backslashes are special even in single quotes.
+fn fish_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Generate completion of operators and their flags, with zsh completing targets by ids.
+fn completions(shell: Shell) -> String {
+    let mut script = vec![];
+    Operator::clap().gen_completions_to(
+        env!("CARGO_PKG_NAME"),
+        match shell {
+            Shell::Bash => clap::Shell::Bash,
+            Shell::Zsh => clap::Shell::Zsh,
+            Shell::Fish => clap::Shell::Fish,
+        },
+        &mut script,
+    );
+    let script = String::from_utf8_lossy(&script);
+    match shell {
+        // Script is meant to be autoloaded so it ends by calling the completion function.
+        Shell::Zsh => script
+            .lines()
+            .filter(|line| *line != concat!("_", env!("CARGO_PKG_NAME"), r#" "$@""#))
+            .map(
+                |line| match line.starts_with("':target") || line.starts_with("'::target") {
+                    true => line.replace(":_files'", ":_osoy_id_candidates'"),
+                    false => line.into(),
+                },
+            )
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => script.into(),
+    }
+}
+
+/// Generate script defining function `cmd` along with completion and prompt helper.
+pub fn script(shell: Shell, cmd: &str, config: &Config) -> String {
+    let src = config.src.display().to_string();
+    let (template, src) = match shell {
+        Shell::Bash => ([POSIX, BASH].concat(), shell_quote(&src)),
+        Shell::Zsh => ([POSIX, ZSH].concat(), shell_quote(&src)),
+        Shell::Fish => (FISH.to_string(), fish_quote(&src)),
+    };
+    template
+        .replace("{cmd}", cmd)
+        .replace("{osoy}", env!("CARGO_PKG_NAME"))
+        .replace("{src}", &src)
+        .replace("{completions}", &completions(shell))
+}
+
+impl Exec for Opt {
+    fn exec(self, config: Config) -> i32 {
+        if self.cmd.is_empty()
+            || !self
+                .cmd
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+        {
+            config
+                .reporter
+                .info(&format!("invalid function name '{}'", self.cmd));
+            return 1;
+        }
+        print!("{}", script(self.shell, &self.cmd, &config));
+        0
+    }
+}
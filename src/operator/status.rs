@@ -0,0 +1,124 @@
+use crate::gitutil::RepoStatus;
+use crate::operator::SelectOpt;
+use crate::repo;
+use crate::time::{format_age, now};
+use crate::{report, Config, Exec, Location};
+use git2::Repository;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(alias = "st", about = "Show summary of repository statuses")]
+pub struct Opt {
+    #[structopt(short, long, help = "Use regular expressions")]
+    pub regex: bool,
+    #[structopt(flatten)]
+    pub select: SelectOpt,
+    #[structopt(short, long, help = "Show only repositories with changes")]
+    pub dirty: bool,
+    #[structopt(short, long, help = "Show only repositories ahead of upstream")]
+    pub ahead: bool,
+    #[structopt(short, long, help = "Show only repositories behind upstream")]
+    pub behind: bool,
+    #[structopt(long, help = "Show only repositories both ahead and behind upstream")]
+    pub diverged: bool,
+    #[structopt(long, help = "Show only repositories without upstream")]
+    pub no_upstream: bool,
+    #[structopt(help = Location::about())]
+    pub targets: Vec<Location>,
+}
+
+impl Opt {
+    fn filtered(&self) -> bool {
+        self.dirty || self.ahead || self.behind || self.diverged || self.no_upstream
+    }
+
+    /// Whether status passes any of the given filters.
+    fn accepts(&self, stat: &RepoStatus) -> bool {
+        let (ahead, behind) = stat.graph.unwrap_or((0, 0));
+        !self.filtered()
+            || (self.dirty && stat.is_dirty())
+            || (self.ahead && ahead > 0)
+            || (self.behind && behind > 0)
+            || (self.diverged && ahead > 0 && behind > 0)
+            || (self.no_upstream && stat.graph.is_none())
+    }
+}
+
+impl Exec for Opt {
+    fn exec(self, config: Config) -> i32 {
+        let mut errors = 0;
+
+        match repo::iterate_matching_exists(
+            &config.src,
+            self.select
+                .selection(&config, self.targets.clone(), self.regex),
+        ) {
+            Ok(iter) => {
+                let now = now();
+                let mut rows = vec![[
+                    "id",
+                    "branch",
+                    "ahead",
+                    "behind",
+                    "staged",
+                    "unstaged",
+                    "untracked",
+                    "stash",
+                    "age",
+                ]
+                .iter()
+                .map(|head| head.to_string())
+                .collect::<Vec<_>>()];
+
+                for path in iter {
+                    let id = path
+                        .strip_prefix(&config.src)
+                        .unwrap()
+                        .display()
+                        .to_string();
+                    match Repository::open(&path) {
+                        Ok(repository) => {
+                            let stat = RepoStatus::from(&repository);
+                            if self.accepts(&stat) {
+                                let graph = |get: fn((usize, usize)) -> usize| {
+                                    stat.graph
+                                        .map_or("-".into(), |graph| get(graph).to_string())
+                                };
+                                rows.push(vec![
+                                    id,
+                                    stat.branch.clone().unwrap_or_else(|| "-".into()),
+                                    graph(|g| g.0),
+                                    graph(|g| g.1),
+                                    stat.staged().to_string(),
+                                    stat.unstaged().to_string(),
+                                    stat.untracked().to_string(),
+                                    stat.stashes.to_string(),
+                                    stat.last_commit
+                                        .map_or("-".into(), |time| format_age(now - time)),
+                                ]);
+                            }
+                        }
+                        Err(err) => {
+                            errors += 1;
+                            config
+                                .reporter
+                                .info(&format!("could not open '{}': {}", id, err));
+                        }
+                    }
+                }
+
+                if rows.len() > 1 {
+                    for line in report::table(&rows) {
+                        config.reporter.print(&line);
+                    }
+                }
+            }
+            Err(err) => {
+                errors += 1;
+                config.reporter.info(&err.to_string())
+            }
+        }
+
+        errors
+    }
+}
@@ -1,5 +1,5 @@
+use crate::operator::SelectOpt;
 use crate::{link, repo, Config, Exec, Location};
-use std::fs;
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
@@ -7,6 +7,8 @@ use structopt::StructOpt;
 pub struct Opt {
     #[structopt(short, long, help = "Use regular expressions")]
     pub regex: bool,
+    #[structopt(flatten)]
+    pub select: SelectOpt,
     #[structopt(short, long, help = "Do not prompt")]
     pub force: bool,
     #[structopt(short, long, help = "Show detailed output")]
@@ -19,28 +21,35 @@ impl Exec for Opt {
     fn exec(self, config: Config) -> i32 {
         let mut errors = 0;
 
-        match repo::iterate_matching_exists(&config.src, self.targets, self.regex) {
+        match repo::iterate_matching_exists(
+            &config.src,
+            self.select.selection(&config, self.targets, self.regex),
+        ) {
             Ok(iter) => {
                 if let Ok(iter) = link::iterate(&config.bin, iter.collect()) {
                     for (sym, dest) in iter {
                         if self.force
-                            || ask_bool!(
+                            || config.prompter.ask_bool(&format!(
                                 "unlink '{}'?",
                                 dest.strip_prefix(&config.src).unwrap().display()
-                            )
+                            ))
                         {
-                            match fs::remove_file(&sym) {
+                            match link::remove(&sym) {
                                 Ok(_) => {
                                     if self.verbose {
-                                        info!(
+                                        config.reporter.info(&format!(
                                             "removed '{}'",
                                             sym.strip_prefix(&config.bin).unwrap().display()
-                                        );
+                                        ));
                                     }
                                 }
                                 Err(err) => {
                                     errors += 1;
-                                    info!("could not remove '{}': {}", sym.display(), err)
+                                    config.reporter.info(&format!(
+                                        "could not remove '{}': {}",
+                                        sym.display(),
+                                        err
+                                    ))
                                 }
                             }
                         }
@@ -49,7 +58,7 @@ impl Exec for Opt {
             }
             Err(err) => {
                 errors += 1;
-                info!("{}", err)
+                config.reporter.info(&err.to_string())
             }
         }
 
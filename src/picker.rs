@@ -0,0 +1,210 @@
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::AlternateScreen;
+use termion::{clear, cursor, style};
+
+/// Result of handling a key.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Pending,
+    Cancelled,
+    /// Indices of chosen options.
+    Chosen(Vec<usize>),
+}
+
+/// State of choosing options from a filtered list.
+#[derive(Debug)]
+pub struct Picker {
+    options: Vec<String>,
+    multiple: bool,
+    filter: String,
+    /// Position of cursor among visible options.
+    cursor: usize,
+    chosen: BTreeSet<usize>,
+}
+
+impl Picker {
+    pub fn new(options: Vec<String>, multiple: bool) -> Self {
+        Self {
+            options,
+            multiple,
+            filter: String::new(),
+            cursor: 0,
+            chosen: BTreeSet::new(),
+        }
+    }
+
+    /// Get indices of options containing the characters of filter in order, ignoring case.
+    pub fn visible(&self) -> Vec<usize> {
+        let filter = self.filter.to_lowercase();
+        self.options
+            .iter()
+            .enumerate()
+            .filter(|(_, option)| {
+                let mut chars = option
+                    .to_lowercase()
+                    .chars()
+                    .collect::<Vec<_>>()
+                    .into_iter();
+                filter.chars().all(|ch| chars.any(|other| other == ch))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    pub fn handle(&mut self, key: Key) -> Outcome {
+        let visible = self.visible();
+        match key {
+            Key::Esc | Key::Ctrl('c') | Key::Ctrl('d') => return Outcome::Cancelled,
+            Key::Char('\n') => {
+                return match (self.chosen.is_empty(), visible.get(self.cursor)) {
+                    (false, _) => Outcome::Chosen(self.chosen.iter().cloned().collect()),
+                    (true, Some(index)) => Outcome::Chosen(vec![*index]),
+                    (true, None) => Outcome::Pending,
+                };
+            }
+            Key::Up | Key::Ctrl('p') => self.cursor = self.cursor.saturating_sub(1),
+            Key::Down | Key::Ctrl('n') => {
+                self.cursor = (self.cursor + 1).min(visible.len().saturating_sub(1))
+            }
+            Key::Char('\t') if self.multiple => {
+                if let Some(index) = visible.get(self.cursor) {
+                    if !self.chosen.remove(index) {
+                        self.chosen.insert(*index);
+                    }
+                }
+                self.cursor = (self.cursor + 1).min(visible.len().saturating_sub(1));
+            }
+            Key::Backspace => {
+                self.filter.pop();
+                self.cursor = 0;
+            }
+            Key::Char(ch) if !ch.is_control() => {
+                self.filter.push(ch);
+                self.cursor = 0;
+            }
+            _ => {}
+        }
+        Outcome::Pending
+    }
+
+    /// Render question, filter and as many visible options as fit in height.
+    fn draw(&self, out: &mut impl Write, question: &str, height: usize) -> io::Result<()> {
+        write!(out, "{}{}", clear::All, cursor::Goto(1, 1))?;
+        write!(out, "{} {}\r\n", question, self.filter)?;
+        let visible = self.visible();
+        let rows = height.saturating_sub(2).max(1);
+        let offset = (self.cursor + 1).saturating_sub(rows);
+        for (position, index) in visible.iter().enumerate().skip(offset).take(rows) {
+            let mark = match (self.multiple, self.chosen.contains(index)) {
+                (false, _) => "",
+                (true, true) => "[x] ",
+                (true, false) => "[ ] ",
+            };
+            match position == self.cursor {
+                true => write!(
+                    out,
+                    "{}> {}{}{}\r\n",
+                    style::Invert,
+                    mark,
+                    self.options[*index],
+                    style::Reset
+                )?,
+                false => write!(out, "  {}{}\r\n", mark, self.options[*index])?,
+            }
+        }
+        write!(
+            out,
+            "{}/{}{}",
+            visible.len(),
+            self.options.len(),
+            match self.multiple {
+                true => " (tab to select, enter to confirm)",
+                false => "",
+            }
+        )?;
+        out.flush()
+    }
+}
+
+/// Let user choose options on the terminal, returning `None` if cancelled.
+/// Terminal is accessed directly so that output of the process may be captured.
+pub fn pick(
+    question: &str,
+    options: Vec<String>,
+    multiple: bool,
+) -> io::Result<Option<Vec<usize>>> {
+    let tty = termion::get_tty()?;
+    let input = tty.try_clone()?;
+    let mut out = AlternateScreen::from(tty.into_raw_mode()?);
+    let height = termion::terminal_size().map_or(24, |(_, height)| height as usize);
+    let mut picker = Picker::new(options, multiple);
+
+    write!(out, "{}", cursor::Hide)?;
+    picker.draw(&mut out, question, height)?;
+    let mut outcome = Outcome::Cancelled;
+    for key in input.keys() {
+        match picker.handle(key?) {
+            Outcome::Pending => picker.draw(&mut out, question, height)?,
+            done => {
+                outcome = done;
+                break;
+            }
+        }
+    }
+    write!(out, "{}", cursor::Show)?;
+    out.flush()?;
+    Ok(match outcome {
+        Outcome::Chosen(indices) => Some(indices),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picker(multiple: bool) -> Picker {
+        Picker::new(
+            vec![
+                "github.com/osoy/osoy".into(),
+                "gitlab.com/group/tool".into(),
+                "gitlab.com/group/sub/tool".into(),
+            ],
+            multiple,
+        )
+    }
+
+    #[test]
+    fn filter_and_choose() {
+        let mut picker = picker(false);
+        for ch in "GLtl".chars() {
+            assert_eq!(picker.handle(Key::Char(ch)), Outcome::Pending);
+        }
+        assert_eq!(picker.visible(), vec![1, 2]);
+        picker.handle(Key::Down);
+        picker.handle(Key::Down);
+        assert_eq!(picker.handle(Key::Char('\n')), Outcome::Chosen(vec![2]));
+
+        picker.handle(Key::Char('x'));
+        assert!(picker.visible().is_empty());
+        assert_eq!(picker.handle(Key::Char('\n')), Outcome::Pending);
+        picker.handle(Key::Backspace);
+        assert_eq!(picker.handle(Key::Char('\n')), Outcome::Chosen(vec![1]));
+        assert_eq!(picker.handle(Key::Esc), Outcome::Cancelled);
+    }
+
+    #[test]
+    fn choose_multiple() {
+        let mut picker = picker(true);
+        picker.handle(Key::Char('\t'));
+        picker.handle(Key::Char('\t'));
+        picker.handle(Key::Up);
+        picker.handle(Key::Char('\t'));
+        picker.handle(Key::Char('\t'));
+        assert_eq!(picker.handle(Key::Char('\n')), Outcome::Chosen(vec![0, 2]));
+    }
+}
@@ -0,0 +1,378 @@
+use crate::config::Settings;
+use crate::gitutil::RepoStatus;
+use crate::time::{now, parse_age};
+use crate::{link, Location};
+use git2::Repository;
+use std::path::Path;
+use std::str::FromStr;
+use std::{error, fmt};
+
+/// Property of repository which predicates test.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Field {
+    Dirty,
+    Ahead,
+    Behind,
+    Diverged,
+    Upstream,
+    Stashes,
+    Branch,
+    Host,
+    LastCommit,
+    Exe,
+}
+
+impl Field {
+    pub const NAMES: [&'static str; 10] = [
+        "dirty",
+        "ahead",
+        "behind",
+        "diverged",
+        "upstream",
+        "stashes",
+        "branch",
+        "host",
+        "last-commit",
+        "exe",
+    ];
+
+    const ALL: [Field; 10] = [
+        Field::Dirty,
+        Field::Ahead,
+        Field::Behind,
+        Field::Diverged,
+        Field::Upstream,
+        Field::Stashes,
+        Field::Branch,
+        Field::Host,
+        Field::LastCommit,
+        Field::Exe,
+    ];
+
+    fn name(self) -> &'static str {
+        Self::NAMES[Self::ALL.iter().position(|field| *field == self).unwrap()]
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    /// Operators ordered so that none is a prefix of a later one.
+    const SYMBOLS: [(&'static str, Comparison); 6] = [
+        ("!=", Comparison::Ne),
+        ("<=", Comparison::Le),
+        (">=", Comparison::Ge),
+        ("=", Comparison::Eq),
+        ("<", Comparison::Lt),
+        (">", Comparison::Gt),
+    ];
+
+    fn holds<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Gt => left > right,
+            Comparison::Ge => left >= right,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Test {
+    /// Flag is set or count is above zero.
+    Present,
+    Count(Comparison, usize),
+    /// Age in seconds.
+    Age(Comparison, i64),
+    /// Glob pattern, negated if comparison is `!=`.
+    Text(Comparison, String),
+}
+
+/// Condition on the state of a repository such as `dirty`, `!exe`, `branch=main` or
+/// `last-commit>2y`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Predicate {
+    negate: bool,
+    field: Field,
+    test: Test,
+}
+
+/// State of repository gathered once for testing predicates.
+pub struct Facts {
+    status: RepoStatus,
+    host: Option<String>,
+    exe: bool,
+}
+
+impl Facts {
+    /// Gather only the state which given predicates test, with links looked up in `bin`.
+    pub fn read(
+        path: &Path,
+        bin: &Path,
+        settings: &Settings,
+        predicates: &[Predicate],
+    ) -> Result<Self, git2::Error> {
+        let needs = |fields: &[Field]| {
+            predicates
+                .iter()
+                .any(|predicate| fields.contains(&predicate.field))
+        };
+        let repo = Repository::open(path)?;
+        let status = match needs(&[
+            Field::Dirty,
+            Field::Ahead,
+            Field::Behind,
+            Field::Diverged,
+            Field::Upstream,
+            Field::Stashes,
+            Field::Branch,
+            Field::LastCommit,
+        ]) {
+            true => RepoStatus::read(&repo, needs(&[Field::Dirty])),
+            false => RepoStatus::default(),
+        };
+        let host = match needs(&[Field::Host]) {
+            true => repo
+                .find_remote("origin")
+                .ok()
+                .and_then(|remote| Location::parse_with(remote.url()?, settings).ok())
+                .map(|location| location.domain()),
+            false => None,
+        };
+        Ok(Self {
+            status,
+            host,
+            exe: needs(&[Field::Exe])
+                && (link::linkables(path).is_ok_and(|linkables| !linkables.is_empty())
+                    || link::iterate(bin, vec![path.into()])
+                        .is_ok_and(|mut links| links.next().is_some())),
+        })
+    }
+}
+
+impl Predicate {
+    pub fn test(&self, facts: &Facts) -> bool {
+        let status = &facts.status;
+        let (ahead, behind) = status.graph.unwrap_or((0, 0));
+        let count = |comparison: Comparison, right| match self.field {
+            Field::Ahead => comparison.holds(ahead, right),
+            Field::Behind => comparison.holds(behind, right),
+            _ => comparison.holds(status.stashes, right),
+        };
+        let result = match &self.test {
+            Test::Present => match self.field {
+                Field::Dirty => status.is_dirty(),
+                Field::Diverged => ahead > 0 && behind > 0,
+                Field::Upstream => status.graph.is_some(),
+                Field::Exe => facts.exe,
+                _ => count(Comparison::Gt, 0),
+            },
+            Test::Count(comparison, right) => count(*comparison, *right),
+            Test::Age(comparison, right) => status
+                .last_commit
+                .is_some_and(|time| comparison.holds(now() - time, *right)),
+            Test::Text(comparison, pattern) => {
+                let value = match self.field {
+                    Field::Branch => status.branch.as_deref(),
+                    _ => facts.host.as_deref(),
+                };
+                let matches = value.is_some_and(|value| {
+                    glob::Pattern::new(pattern).map_or(pattern == value, |glob| glob.matches(value))
+                });
+                matches == (*comparison == Comparison::Eq)
+            }
+        };
+        result != self.negate
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsePredicateError(String);
+
+impl fmt::Display for ParsePredicateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl error::Error for ParsePredicateError {}
+
+impl FromStr for Predicate {
+    type Err = ParsePredicateError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negate, s) = match s.trim().strip_prefix('!') {
+            Some(rest) => (true, rest.trim()),
+            None => (false, s.trim()),
+        };
+        let split = s.find(['=', '!', '<', '>']).unwrap_or(s.len());
+        let (name, rest) = s.split_at(split);
+        let name = name.trim();
+        let field = Field::NAMES
+            .iter()
+            .position(|field| *field == name)
+            .map(|index| Field::ALL[index])
+            .ok_or_else(|| {
+                ParsePredicateError(format!(
+                    "unknown field '{}', expected one of {}",
+                    name,
+                    Field::NAMES.join(", ")
+                ))
+            })?;
+        let comparison = Comparison::SYMBOLS
+            .iter()
+            .find(|(symbol, _)| rest.starts_with(symbol));
+        let test = match (field, comparison) {
+            (_, None) if !rest.is_empty() => {
+                return Err(ParsePredicateError(format!(
+                    "invalid comparison '{}'",
+                    rest
+                )))
+            }
+            (Field::Branch | Field::Host | Field::LastCommit, None) => {
+                return Err(ParsePredicateError(format!(
+                    "'{}' needs a comparison, e.g. {}",
+                    name,
+                    match field {
+                        Field::LastCommit => "last-commit>2y",
+                        _ => "branch=main",
+                    }
+                )))
+            }
+            (_, None) => Test::Present,
+            (Field::Dirty | Field::Diverged | Field::Upstream | Field::Exe, Some(_)) => {
+                return Err(ParsePredicateError(format!(
+                    "'{}' can not be compared, use '{}' or '!{}'",
+                    name, name, name
+                )))
+            }
+            (_, Some((symbol, comparison))) => {
+                let value = rest[symbol.len()..].trim();
+                match field {
+                    Field::LastCommit => {
+                        Test::Age(*comparison, parse_age(value).map_err(ParsePredicateError)?)
+                    }
+                    Field::Branch | Field::Host => match comparison {
+                        Comparison::Eq | Comparison::Ne => {
+                            Test::Text(*comparison, value.to_string())
+                        }
+                        _ => {
+                            return Err(ParsePredicateError(format!(
+                                "'{}' can only be compared with = or !=",
+                                name
+                            )))
+                        }
+                    },
+                    _ => Test::Count(
+                        *comparison,
+                        value.parse().map_err(|_| {
+                            ParsePredicateError(format!("invalid count '{}'", value))
+                        })?,
+                    ),
+                }
+            }
+        };
+        Ok(Self {
+            negate,
+            field,
+            test,
+        })
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negate {
+            write!(f, "!")?;
+        }
+        write!(f, "{}", self.field.name())?;
+        let symbol = |comparison: &Comparison| {
+            Comparison::SYMBOLS
+                .iter()
+                .find(|(_, other)| other == comparison)
+                .map_or("", |(symbol, _)| symbol)
+        };
+        match &self.test {
+            Test::Present => Ok(()),
+            Test::Count(comparison, count) => write!(f, "{}{}", symbol(comparison), count),
+            Test::Age(comparison, age) => write!(f, "{}{}s", symbol(comparison), age),
+            Test::Text(comparison, text) => write!(f, "{}{}", symbol(comparison), text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facts() -> Facts {
+        Facts {
+            status: RepoStatus {
+                changes: Some(vec![('M', "README".into())]),
+                branch: Some("feature/parser".into()),
+                graph: Some((2, 0)),
+                stashes: 1,
+                last_commit: Some(now() - 3 * 24 * 3600),
+            },
+            host: Some("gitlab.com".into()),
+            exe: false,
+        }
+    }
+
+    fn test(s: &str) -> bool {
+        Predicate::from_str(s).unwrap().test(&facts())
+    }
+
+    #[test]
+    fn evaluation() {
+        assert!(test("dirty"));
+        assert!(test("ahead"));
+        assert!(!test("behind"));
+        assert!(test("!behind"));
+        assert!(!test("diverged"));
+        assert!(test("upstream"));
+        assert!(test("ahead>=2"));
+        assert!(!test("ahead>2"));
+        assert!(test("stashes=1"));
+        assert!(test("branch=feature/*"));
+        assert!(test("branch != main"));
+        assert!(test("host=gitlab.com"));
+        assert!(!test("host=github.com"));
+        assert!(test("last-commit>2d"));
+        assert!(test("last-commit<1w"));
+        assert!(!test("exe"));
+        assert!(test("!exe"));
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(
+            Predicate::from_str("! last-commit > 2y")
+                .unwrap()
+                .to_string(),
+            format!("!last-commit>{}s", 2 * 365 * 24 * 3600)
+        );
+        for invalid in [
+            "stale",
+            "branch",
+            "last-commit",
+            "dirty=1",
+            "ahead=many",
+            "branch<main",
+            "ahead=>1",
+            "last-commit>2x",
+            "last-commit>999999999999y",
+        ] {
+            assert!(Predicate::from_str(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
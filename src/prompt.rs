@@ -0,0 +1,128 @@
+use crate::picker;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::{fmt, io};
+
+/// Source of answers to questions asked from the user.
+pub trait Prompter: Send + Sync {
+    /// Ask for a line of input.
+    fn ask_string(&self, question: &str) -> Option<String>;
+    /// Ask for a line of input without echoing it.
+    fn ask_secret(&self, question: &str) -> Option<String>;
+    /// Ask a yes or no question where anything but yes means no.
+    fn ask_bool(&self, question: &str) -> bool {
+        self.ask_string(question)
+            .is_some_and(|answer| matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+    /// Ask to choose one or if multiple is set any of the options returning their indices,
+    /// or `None` if cancelled or choosing is not possible.
+    fn pick(&self, question: &str, options: &[String], multiple: bool) -> Option<Vec<usize>>;
+}
+
+impl fmt::Debug for dyn Prompter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "Prompter".fmt(f)
+    }
+}
+
+/// Ask questions on the terminal exiting the process when input is closed.
+#[derive(Default)]
+pub struct Terminal {}
+
+impl Prompter for Terminal {
+    fn ask_string(&self, question: &str) -> Option<String> {
+        Some(ask_string!("{}", question))
+    }
+
+    fn ask_secret(&self, question: &str) -> Option<String> {
+        Some(ask_secret!("{}", question))
+    }
+
+    fn pick(&self, question: &str, options: &[String], multiple: bool) -> Option<Vec<usize>> {
+        match termion::is_tty(&io::stdin()) {
+            true => picker::pick(question, options.to_vec(), multiple)
+                .ok()
+                .flatten(),
+            false => None,
+        }
+    }
+}
+
+/// Answer questions from a predefined list, meant for tests.
+#[derive(Default)]
+pub struct Scripted {
+    answers: Mutex<VecDeque<String>>,
+    questions: Mutex<Vec<String>>,
+}
+
+impl Scripted {
+    pub fn new<I, S>(answers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            answers: Mutex::new(answers.into_iter().map(Into::into).collect()),
+            questions: Mutex::new(vec![]),
+        }
+    }
+
+    /// Questions asked so far.
+    pub fn questions(&self) -> Vec<String> {
+        self.questions.lock().unwrap().clone()
+    }
+
+    fn answer(&self, question: &str) -> Option<String> {
+        self.questions.lock().unwrap().push(question.into());
+        self.answers.lock().unwrap().pop_front()
+    }
+}
+
+impl Prompter for Scripted {
+    fn ask_string(&self, question: &str) -> Option<String> {
+        self.answer(question)
+    }
+
+    fn ask_secret(&self, question: &str) -> Option<String> {
+        self.answer(question)
+    }
+
+    /// Answer is taken as whitespace separated numbers of options starting from 1.
+    fn pick(&self, question: &str, options: &[String], multiple: bool) -> Option<Vec<usize>> {
+        let indices = self
+            .answer(question)?
+            .split_whitespace()
+            .map(|number| number.parse::<usize>().ok()?.checked_sub(1))
+            .collect::<Option<Vec<_>>>()?;
+        (!indices.is_empty()
+            && (multiple || indices.len() == 1)
+            && indices.iter().all(|index| *index < options.len()))
+        .then_some(indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripted() {
+        let prompter = Scripted::new(vec!["yes", "n", "secret"]);
+        assert!(prompter.ask_bool("first?"));
+        assert!(!prompter.ask_bool("second?"));
+        assert_eq!(prompter.ask_secret("third:"), Some("secret".into()));
+        assert!(!prompter.ask_bool("fourth?"));
+        assert_eq!(
+            prompter.questions(),
+            vec!["first?", "second?", "third:", "fourth?"]
+        );
+
+        let options = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let prompter = Scripted::new(vec!["2", "1 3", "1 3", "4"]);
+        assert_eq!(prompter.pick("pick", &options, false), Some(vec![1]));
+        assert_eq!(prompter.pick("pick", &options, true), Some(vec![0, 2]));
+        assert_eq!(prompter.pick("pick", &options, false), None);
+        assert_eq!(prompter.pick("pick", &options, false), None);
+        assert_eq!(prompter.pick("pick", &options, false), None);
+    }
+}
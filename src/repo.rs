@@ -1,5 +1,12 @@
-use crate::{link, Location};
+use crate::config::Settings;
+use crate::gitutil::{RepoAudit, RepoStatus};
+use crate::location::Matching;
+use crate::predicate::{Facts, Predicate};
+use crate::report::{self, Reporter};
+use crate::{link, Config, Location};
+use git2::Repository;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::{fs, io, iter};
 
 /// Get recursive iterator over git repositories in given directory.
@@ -18,7 +25,7 @@ pub fn iterate(dir: &Path) -> io::Result<Box<dyn Iterator<Item = PathBuf> + Send
             Err(err) => Err(io::Error::new(
                 err.kind(),
                 match err.kind() {
-                    io::ErrorKind::NotFound => format!("no repositories found"),
+                    io::ErrorKind::NotFound => "no repositories found".to_string(),
                     _ => format!("could not access '{}': {}", dir.display(), err),
                 },
             )),
@@ -26,58 +33,357 @@ pub fn iterate(dir: &Path) -> io::Result<Box<dyn Iterator<Item = PathBuf> + Send
     }
 }
 
-/// Get iterator over repositories that match any of the given targets.
+/// Get repository in directory which contains given path.
+pub fn owner(dir: &Path, path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .take_while(|ancestor| ancestor.starts_with(dir) && *ancestor != dir)
+        .find(|ancestor| ancestor.join(".git").exists())
+        .map(PathBuf::from)
+}
+
+/// Repositories to operate on given by targets, excluded patterns and tags of Osoy home.
+#[derive(Debug, Clone)]
+pub struct Selection {
+    targets: Vec<Location>,
+    matching: Matching,
+    exclude: Vec<Location>,
+    predicates: Vec<Predicate>,
+    /// Osoy home whose settings define tags.
+    home: PathBuf,
+    /// Directory of links telling which executables are linked.
+    bin: PathBuf,
+    reporter: Arc<dyn Reporter>,
+}
+
+impl Selection {
+    /// Select repositories matching any of the targets, or all if there are none.
+    pub fn new(config: &Config, targets: Vec<Location>, matching: Matching) -> Self {
+        Self {
+            targets,
+            matching,
+            exclude: vec![],
+            predicates: vec![],
+            home: config.home.clone(),
+            bin: config.bin.clone(),
+            reporter: config.reporter.clone(),
+        }
+    }
+
+    /// Leave out repositories matching any of the glob patterns.
+    pub fn with_exclude(self, exclude: Vec<Location>) -> Self {
+        Self { exclude, ..self }
+    }
+
+    /// Leave out repositories whose state does not satisfy all of the predicates.
+    pub fn with_predicates(self, predicates: Vec<Predicate>) -> Self {
+        Self { predicates, ..self }
+    }
+
+    /// Apply settings, resolve tags and compile regular expressions.
+    pub fn matcher(self) -> io::Result<Matcher> {
+        let settings = Settings::read(&self.home)?;
+        let all = self.targets.is_empty();
+        let mut targets = resolve(&settings, self.targets, self.matching, &mut vec![])?;
+        if self.matching == Matching::Regex {
+            for (location, _) in targets.iter_mut() {
+                location
+                    .check_regex()
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err.to_string()))?;
+            }
+        }
+        Ok(Matcher {
+            all,
+            predicates: self.predicates,
+            targets,
+            exclude: resolve(&settings, self.exclude, Matching::Glob, &mut vec![])?,
+            settings,
+            bin: self.bin,
+            reporter: self.reporter,
+        })
+    }
+}
+
+/// Replace tags like `@work` by the glob patterns they are defined as in settings.
+fn resolve(
+    settings: &Settings,
+    locations: Vec<Location>,
+    matching: Matching,
+    seen: &mut Vec<String>,
+) -> io::Result<Vec<(Location, Matching)>> {
+    let mut resolved = vec![];
+    for location in locations {
+        let tag = match location.tag() {
+            Some(tag) => tag.to_string(),
+            None => {
+                let display = location.to_string();
+                let location = location.with_settings(settings).map_err(|err| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("invalid location '{}': {}", display, err),
+                    )
+                })?;
+                resolved.push((location, matching));
+                continue;
+            }
+        };
+        if seen.contains(&tag) {
+            continue;
+        }
+        let patterns = settings
+            .tags
+            .get(&tag)
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, format!("unknown tag '@{}'", tag))
+            })?
+            .iter()
+            .map(|pattern| {
+                Location::parse_with(pattern, settings).map_err(|err| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid pattern '{}' of tag '@{}': {}", pattern, tag, err),
+                    )
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        seen.push(tag);
+        resolved.extend(resolve(settings, patterns, Matching::Glob, seen)?);
+    }
+    Ok(resolved)
+}
+
+/// Selection with tags resolved.
+#[derive(Debug)]
+pub struct Matcher {
+    /// Whether all repositories are selected as no targets were given.
+    all: bool,
+    targets: Vec<(Location, Matching)>,
+    exclude: Vec<(Location, Matching)>,
+    predicates: Vec<Predicate>,
+    settings: Settings,
+    bin: PathBuf,
+    reporter: Arc<dyn Reporter>,
+}
+
+impl Matcher {
+    /// Check whether id path relative to source directory is selected.
+    pub fn matches(&mut self, id: &Path) -> bool {
+        (self.all
+            || self
+                .targets
+                .iter_mut()
+                .any(|(location, matching)| location.matches_with(id, *matching)))
+            && !self
+                .exclude
+                .iter_mut()
+                .any(|(location, matching)| location.matches_with(id, *matching))
+    }
+
+    /// Check whether state of repository satisfies all predicates.
+    /// Repositories whose state could not be read are reported and left out.
+    pub fn satisfies(&self, path: &Path) -> bool {
+        if self.predicates.is_empty() {
+            return true;
+        }
+        match Facts::read(path, &self.bin, &self.settings, &self.predicates) {
+            Ok(facts) => self
+                .predicates
+                .iter()
+                .all(|predicate| predicate.test(&facts)),
+            Err(err) => {
+                self.reporter.info(&format!(
+                    "could not read state of '{}': {}",
+                    path.display(),
+                    err.message()
+                ));
+                false
+            }
+        }
+    }
+
+    /// Get the best score of fuzzy targets matching id.
+    fn fuzzy_score(&self, id: &Path) -> Option<i64> {
+        self.targets
+            .iter()
+            .filter(|(_, matching)| *matching == Matching::Fuzzy)
+            .filter_map(|(location, _)| location.fuzzy_score(id))
+            .max()
+    }
+}
+
+/// Get iterator over repositories that are selected.
 pub fn iterate_matching(
     dir: &Path,
-    mut targets: Vec<Location>,
-    regex: bool,
+    selection: Selection,
 ) -> io::Result<impl Iterator<Item = PathBuf>> {
-    Ok(iterate(dir)?.filter(move |path| {
-        targets.len() == 0
-            || targets.iter_mut().any(|location| match regex {
-                true => location.matches_re(&path),
-                false => location.matches(&path),
-            })
+    let mut matcher = selection.matcher()?;
+    let dir = dir.to_path_buf();
+    Ok(iterate(&dir)?.filter(move |path| {
+        matcher.matches(path.strip_prefix(&dir).unwrap_or(path)) && matcher.satisfies(path)
     }))
 }
 
 /// Same as `iterate_matching` except returns an error if no matching repositories found.
 pub fn iterate_matching_exists(
     dir: &Path,
-    targets: Vec<Location>,
-    regex: bool,
+    selection: Selection,
 ) -> io::Result<impl Iterator<Item = PathBuf>> {
-    let mut repos = iterate_matching(dir, targets, regex)?;
+    let mut repos = iterate_matching(dir, selection)?;
     match repos.next() {
         Some(first) => Ok(iter::once(first).chain(repos)),
-        None => Err(io::Error::new(
-            io::ErrorKind::Other,
-            "no matching entities found",
-        )),
+        None => Err(io::Error::other("no matching entities found")),
     }
 }
 
-/// Get an unique repository in directory otherwise return an error.
-pub fn unique(dir: &Path, target: Location, regex: bool) -> io::Result<PathBuf> {
-    let mut repos = iterate_matching(dir, vec![target.clone()], regex)?;
-    let repo = repos.next();
-    match repos.next() {
-        Some(_) => Err(io::Error::new(
-            io::ErrorKind::Other,
-            "multiple entities match query",
-        )),
-        None => match repo {
-            Some(repo) => Ok(repo),
-            None => Err(io::Error::new(
-                io::ErrorKind::Other,
-                "no entities match query",
-            )),
+/// Count of alternatives listed when query is ambiguous.
+const ALTERNATIVES: usize = 5;
+
+/// Get selected repositories as their fuzzy score, id and path ordered from best to worst.
+fn ranked(dir: &Path, selection: Selection) -> io::Result<Vec<(i64, PathBuf, PathBuf)>> {
+    let mut matcher = selection.matcher()?;
+    let mut repos = iterate(dir)?
+        .filter_map(|path| {
+            let id = path.strip_prefix(dir).unwrap_or(&path).to_path_buf();
+            (matcher.matches(&id) && matcher.satisfies(&path))
+                .then(|| (matcher.fuzzy_score(&id).unwrap_or_default(), id, path))
+        })
+        .collect::<Vec<_>>();
+    repos.sort_by(|(a_score, a_id, _), (b_score, b_id, _)| {
+        b_score.cmp(a_score).then_with(|| a_id.cmp(b_id))
+    });
+    Ok(repos)
+}
+
+/// Get the only or the clearly best ranked repository.
+fn best(repos: &[(i64, PathBuf, PathBuf)]) -> io::Result<Option<PathBuf>> {
+    match repos {
+        [] => Err(io::Error::other("no entities match query")),
+        [(_, _, repo)] => Ok(Some(repo.clone())),
+        [(best, _, repo), (second, _, _), ..] if best > second => Ok(Some(repo.clone())),
+        _ => Ok(None),
+    }
+}
+
+/// Get error listing the best alternatives of ambiguous query.
+fn ambiguous(repos: &[(i64, PathBuf, PathBuf)]) -> io::Error {
+    io::Error::other(
+        repos.iter().take(ALTERNATIVES).fold(
+            "multiple entities match query".to_string(),
+            |state, (_, id, _)| format!("{}\n  {}", state, id.display()),
+        ) + &match repos.len().saturating_sub(ALTERNATIVES) {
+            0 => "".into(),
+            more => format!("\n  and {} more", more),
         },
+    )
+}
+
+/// Ask user to choose from repositories described by their branch and state.
+fn choose(
+    config: &Config,
+    repos: &[(i64, PathBuf, PathBuf)],
+    multiple: bool,
+) -> Option<Vec<PathBuf>> {
+    let rows = repos
+        .iter()
+        .map(|(_, id, path)| {
+            let status = Repository::open(path)
+                .map(|repo| RepoStatus::from(&repo))
+                .ok();
+            vec![
+                id.display().to_string(),
+                status
+                    .as_ref()
+                    .and_then(|status| status.branch.clone())
+                    .unwrap_or_default(),
+                status.map_or("".into(), |status| {
+                    [
+                        status.is_dirty().then(|| "dirty".to_string()),
+                        match status.graph {
+                            Some((0, 0)) => None,
+                            Some((ahead, behind)) => Some(format!("+{} -{}", ahead, behind)),
+                            None => Some("no upstream".into()),
+                        },
+                    ]
+                    .iter()
+                    .flatten()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                }),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let question = match multiple {
+        true => "pick repositories:",
+        false => "pick a repository:",
+    };
+    config
+        .prompter
+        .pick(question, &report::table(&rows), multiple)
+        .map(|indices| {
+            indices
+                .into_iter()
+                .filter_map(|index| repos.get(index))
+                .map(|(_, _, path)| path.clone())
+                .collect()
+        })
+}
+
+/// Get an unique repository in directory otherwise return an error listing the best
+/// alternatives. With fuzzy matching the best scoring repository is picked unless tied.
+pub fn unique(dir: &Path, selection: Selection) -> io::Result<PathBuf> {
+    let repos = ranked(dir, selection)?;
+    best(&repos)?.ok_or_else(|| ambiguous(&repos))
+}
+
+/// Same as `unique` except user is asked to pick one of ambiguous repositories if possible.
+pub fn unique_or_pick(config: &Config, selection: Selection) -> io::Result<PathBuf> {
+    let repos = ranked(&config.src, selection)?;
+    match best(&repos)? {
+        Some(repo) => Ok(repo),
+        None => choose(config, &repos, false)
+            .and_then(|paths| paths.into_iter().next())
+            .ok_or_else(|| ambiguous(&repos)),
+    }
+}
+
+/// Same as `unique_or_pick` except the best fuzzy match is taken only if no other repository
+/// matches, its id matches target as a glob pattern or user confirms it. Meant for operators
+/// whose effect is not easily reverted.
+pub fn unique_or_confirm(config: &Config, selection: Selection) -> io::Result<PathBuf> {
+    let mut exact = Selection {
+        matching: Matching::Glob,
+        ..selection.clone()
+    }
+    .matcher()?;
+    let repos = ranked(&config.src, selection)?;
+    match best(&repos)? {
+        Some(repo)
+            if repos.len() == 1
+                || exact.matches(&repos[0].1)
+                || config
+                    .prompter
+                    .ask_bool(&format!("did you mean '{}'?", repos[0].1.display())) =>
+        {
+            Ok(repo)
+        }
+        _ => choose(config, &repos, false)
+            .and_then(|paths| paths.into_iter().next())
+            .ok_or_else(|| ambiguous(&repos)),
+    }
+}
+
+/// Ask user to pick any of the selected repositories unless only one is selected.
+pub fn pick(config: &Config, selection: Selection) -> io::Result<Vec<PathBuf>> {
+    let repos = ranked(&config.src, selection)?;
+    match repos.as_slice() {
+        [] => Err(io::Error::other("no matching entities found")),
+        [(_, _, repo)] => Ok(vec![repo.clone()]),
+        _ => choose(config, &repos, true).ok_or_else(|| io::Error::other("nothing picked")),
     }
 }
 
 /// Remove empty directories recursively returning remove count.
-fn remove_dir_rec(dir: &Path) -> usize {
+pub fn remove_dir_rec(dir: &Path) -> usize {
     match fs::remove_dir(dir) {
         Ok(_) => match dir.parent() {
             Some(parent) => remove_dir_rec(parent) + 1,
@@ -87,19 +393,77 @@ fn remove_dir_rec(dir: &Path) -> usize {
     }
 }
 
+/// Copy directory recursively keeping symbolic links as they are.
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir(to)?;
+    for entry in from.read_dir()? {
+        let entry = entry?;
+        let (path, dest) = (entry.path(), to.join(entry.file_name()));
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            let link = path.read_link()?;
+            #[cfg(target_family = "unix")]
+            {
+                std::os::unix::fs::symlink(&link, &dest)?;
+            }
+            #[cfg(target_family = "windows")]
+            match path.is_dir() {
+                true => std::os::windows::fs::symlink_dir(&link, &dest)?,
+                false => std::os::windows::fs::symlink_file(&link, &dest)?,
+            }
+        } else if file_type.is_dir() {
+            copy_dir(&path, &dest)?;
+        } else {
+            fs::copy(&path, &dest)?;
+        }
+    }
+    fs::set_permissions(to, from.metadata()?.permissions())
+}
+
+/// Move directory falling back to copying and removing it when crossing file systems.
+pub fn move_dir(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(err) = copy_dir(from, to) {
+                fs::remove_dir_all(to).ok();
+                return Err(io::Error::new(
+                    err.kind(),
+                    format!("could not copy across file systems: {}", err),
+                ));
+            }
+            fs::remove_dir_all(from)
+        }
+        res => res,
+    }
+}
+
+/// Get reasons why removing repository would lose work which only exists locally,
+/// i.e. changes in the index or working tree, unpushed commits, branches and tags, stashes and
+/// untracked files. Removal is safe if there are none.
+pub fn removal_risks(dir: &Path) -> Result<Vec<String>, git2::Error> {
+    let repository = Repository::open(dir)?;
+    let stat = RepoStatus::from(&repository);
+    let mut risks = vec![];
+    if stat.staged() > 0 {
+        risks.push(format!("{} staged changes", stat.staged()));
+    }
+    if stat.unstaged() > 0 {
+        risks.push(format!("{} unstaged changes", stat.unstaged()));
+    }
+    risks.extend(RepoAudit::from(&repository)?.describe());
+    Ok(risks)
+}
+
 /// Remove directory returning a tuple of counts of removed symbolic links and parent directories.
 pub fn remove(bin: &Path, dir: &Path) -> io::Result<(usize, usize)> {
     let res = fs::remove_dir_all(dir);
-    let removed_parents = dir
-        .parent()
-        .map(|parent| remove_dir_rec(parent))
-        .unwrap_or(0);
+    let removed_parents = dir.parent().map(remove_dir_rec).unwrap_or(0);
     res.map(|_| {
         (
             link::iterate(bin, vec![dir.into()])
                 .map(|iter| {
                     iter.fold(0, |acc, (sym, _)| {
-                        acc + fs::remove_file(sym).map(|_| 1).unwrap_or(0)
+                        acc + link::remove(&sym).map(|_| 1).unwrap_or(0)
                     })
                 })
                 .unwrap_or(0),
@@ -108,25 +472,81 @@ pub fn remove(bin: &Path, dir: &Path) -> io::Result<(usize, usize)> {
     })
 }
 
+/// Links pointing into a renamed directory with the outcome of rewriting them.
+pub type Retargeted = Vec<(PathBuf, io::Result<()>)>;
+
 /// Rename directory and remove previous parent directories if empty.
-pub fn rename(target: &Path, dest: &Path) -> io::Result<usize> {
+/// Symbolic links pointing into the directory are rewritten to point to its new location.
+/// Returns the rewritten links and count of removed parent directories.
+pub fn rename(bin: &Path, target: &Path, dest: &Path) -> io::Result<(Retargeted, usize)> {
     match dest.exists() {
         true => Err(io::Error::new(
             io::ErrorKind::AlreadyExists,
             "destination entity already exists",
         )),
         false => {
-            dest.parent().map(|parent| fs::create_dir_all(parent));
-            match fs::rename(target, dest) {
-                Ok(_) => Ok(target
-                    .parent()
-                    .map(|parent| remove_dir_rec(parent))
-                    .unwrap_or(0)),
+            dest.parent().map(fs::create_dir_all);
+            match move_dir(target, dest) {
+                Ok(_) => Ok((
+                    link::iterate(bin, vec![target.into()])
+                        .map(|iter| {
+                            iter.map(|(sym, exe)| {
+                                let exe = dest.join(exe.strip_prefix(target).unwrap());
+                                let res = link::retarget(&sym, &exe);
+                                (sym, res)
+                            })
+                            .collect()
+                        })
+                        .unwrap_or_default(),
+                    target.parent().map(remove_dir_rec).unwrap_or(0),
+                )),
                 Err(err) => {
-                    dest.parent().map(|parent| remove_dir_rec(parent));
+                    dest.parent().map(remove_dir_rec);
                     Err(err)
                 }
             }
         }
     }
 }
+
+/// Get count of rewritten links or an error for the first link which could not be rewritten.
+pub fn retargeted(links: Retargeted) -> io::Result<usize> {
+    let count = links.len();
+    match links
+        .into_iter()
+        .find_map(|(sym, res)| res.err().map(|err| (sym, err)))
+    {
+        Some((sym, err)) => Err(io::Error::new(
+            err.kind(),
+            format!("could not update link '{}': {}", sym.display(), err),
+        )),
+        None => Ok(count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy() {
+        let dir = std::env::temp_dir().join(format!("osoy-copy-dir-{}", std::process::id()));
+        let from = dir.join("from");
+        fs::create_dir_all(from.join("sub")).unwrap();
+        fs::write(from.join("sub/file"), "content").unwrap();
+        #[cfg(target_family = "unix")]
+        std::os::unix::fs::symlink("sub/file", from.join("link")).unwrap();
+
+        let to = dir.join("to");
+        let res = copy_dir(&from, &to);
+        let content = fs::read_to_string(to.join("sub/file"));
+        #[cfg(target_family = "unix")]
+        let link = to.join("link").read_link();
+        fs::remove_dir_all(&dir).unwrap();
+
+        res.unwrap();
+        assert_eq!(content.unwrap(), "content");
+        #[cfg(target_family = "unix")]
+        assert_eq!(link.unwrap(), Path::new("sub/file"));
+    }
+}
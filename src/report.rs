@@ -0,0 +1,409 @@
+use crate::gitutil::ProgressObjects;
+use std::cmp::{max, min};
+use std::io::{stdout, Write};
+use std::sync::{Arc, Mutex, Once};
+use std::{env, fmt};
+
+const OSOY_OUTPUT_VAR: &str = "OSOY_OUTPUT";
+
+/// Sink for everything operators want to tell the user.
+pub trait Reporter: Send + Sync {
+    /// Print a line of primary output.
+    fn print(&self, line: &str);
+    /// Print a diagnostic message.
+    fn info(&self, message: &str);
+    /// Announce that work on repository `id` has started.
+    fn start(&self, id: &str);
+    /// Report the outcome of work on repository `id`.
+    fn result(&self, id: &str, outcome: &str);
+    /// Show combined progress of ongoing object transfers.
+    fn progress(&self, prog: ProgressObjects);
+}
+
+impl fmt::Debug for dyn Reporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "Reporter".fmt(f)
+    }
+}
+
+/// Get reporter by name (`human`, `plain` or `json`).
+pub fn from_name(name: &str) -> Option<Arc<dyn Reporter>> {
+    match name {
+        "human" => Some(Arc::new(Human::default())),
+        "plain" => Some(Arc::new(Plain::default())),
+        "json" => Some(Arc::new(Json::default())),
+        _ => None,
+    }
+}
+
+/// Get reporter named by the `OSOY_OUTPUT` environment variable defaulting to `human`.
+/// An unknown name is reported once on standard error.
+pub fn from_env() -> Arc<dyn Reporter> {
+    static UNKNOWN: Once = Once::new();
+    env::var(OSOY_OUTPUT_VAR)
+        .ok()
+        .filter(|name| !name.is_empty())
+        .and_then(|name| {
+            from_name(&name).or_else(|| {
+                UNKNOWN.call_once(|| {
+                    eprintln!(
+                        "{}",
+                        info_prefix(&format!(
+                            "unknown {} '{}', using human output",
+                            OSOY_OUTPUT_VAR, name
+                        ))
+                    )
+                });
+                None
+            })
+        })
+        .unwrap_or_else(|| Arc::new(Human::default()))
+}
+
+fn info_prefix(message: &str) -> String {
+    format!("{}: {}", env!("CARGO_PKG_NAME"), message)
+}
+
+/// Line based output which remembers a started repository so that its outcome
+/// can be printed on the same line.
+#[derive(Default)]
+pub struct Plain {
+    pending: Mutex<Option<String>>,
+}
+
+impl Plain {
+    fn take_pending(&self, id: &str) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        match pending.as_deref() == Some(id) {
+            true => pending.take().is_some(),
+            false => false,
+        }
+    }
+
+    fn finish_pending(&self) {
+        if self.pending.lock().unwrap().take().is_some() {
+            println!();
+        }
+    }
+}
+
+impl Reporter for Plain {
+    fn print(&self, line: &str) {
+        self.finish_pending();
+        println!("{}", line);
+    }
+
+    fn info(&self, message: &str) {
+        self.finish_pending();
+        eprintln!("{}", info_prefix(message));
+    }
+
+    fn start(&self, id: &str) {
+        self.finish_pending();
+        let stdout = stdout();
+        let mut stdout = stdout.lock();
+        write!(stdout, "{}..", id).ok();
+        stdout.flush().ok();
+        *self.pending.lock().unwrap() = Some(id.into());
+    }
+
+    fn result(&self, id: &str, outcome: &str) {
+        match self.take_pending(id) {
+            true => println!("{}", outcome),
+            false => self.print(&format!("{} {}", id, outcome)),
+        }
+    }
+
+    fn progress(&self, _: ProgressObjects) {}
+}
+
+/// Plain output with a progress bar when attached to a terminal.
+#[derive(Default)]
+pub struct Human {
+    plain: Plain,
+}
+
+impl Reporter for Human {
+    fn print(&self, line: &str) {
+        self.plain.print(line)
+    }
+
+    fn info(&self, message: &str) {
+        self.plain.info(message)
+    }
+
+    fn start(&self, id: &str) {
+        self.plain.start(id)
+    }
+
+    fn result(&self, id: &str, outcome: &str) {
+        self.plain.result(id, outcome)
+    }
+
+    fn progress(&self, prog: ProgressObjects) {
+        let stdout = stdout();
+        if termion::is_tty(&stdout) {
+            let mut stdout = stdout.lock();
+            let space = 50;
+            let (left, mid, right, part) = match prog.total {
+                0 => (0, 0, space, 0),
+                _ => {
+                    let indexed = space * prog.indexed / prog.total;
+                    let received = space * prog.received / prog.total;
+                    let lesser = min(indexed, received);
+                    let greater = max(indexed, received);
+                    (
+                        lesser,
+                        greater - lesser,
+                        space - greater,
+                        100 * min(prog.indexed, prog.received) / prog.total,
+                    )
+                }
+            };
+            write!(
+                stdout,
+                "\r[{}{}{}] {}%",
+                "=".repeat(left),
+                "-".repeat(mid),
+                "·".repeat(right),
+                part
+            )
+            .ok()
+            .map(|_| stdout.flush());
+            write!(stdout, "\r{}", termion::clear::CurrentLine).ok();
+        }
+    }
+}
+
+/// Align columns of rows by padding cells with spaces.
+pub fn table(rows: &[Vec<String>]) -> Vec<String> {
+    let mut widths: Vec<usize> = vec![];
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(i) {
+                Some(max) => *max = (*max).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(i, cell)| format!("{:1$}", cell, widths[i]))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Escape string to be used as a JSON string literal.
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// One JSON object per line on standard output, diagnostics on standard error.
+#[derive(Default)]
+pub struct Json {
+    /// Percentages received and indexed of the last progress written.
+    progress: Mutex<Option<(usize, usize)>>,
+}
+
+impl Json {
+    /// Format progress unless its percentages are the same as of the last one formatted.
+    fn progress_line(&self, prog: ProgressObjects) -> Option<String> {
+        let percent = |count| match prog.total {
+            0 => 0,
+            total => 100 * count / total,
+        };
+        let current = (percent(prog.received), percent(prog.indexed));
+        let mut last = self.progress.lock().unwrap();
+        (*last != Some(current)).then(|| {
+            *last = Some(current);
+            format!(
+                "{{\"type\":\"progress\",\"total\":{},\"received\":{},\"indexed\":{}}}",
+                prog.total, prog.received, prog.indexed
+            )
+        })
+    }
+}
+
+impl Reporter for Json {
+    fn print(&self, line: &str) {
+        println!("{{\"type\":\"line\",\"text\":{}}}", json_string(line));
+    }
+
+    fn info(&self, message: &str) {
+        eprintln!("{{\"type\":\"info\",\"message\":{}}}", json_string(message));
+    }
+
+    fn start(&self, id: &str) {
+        println!("{{\"type\":\"start\",\"id\":{}}}", json_string(id));
+    }
+
+    fn result(&self, id: &str, outcome: &str) {
+        println!(
+            "{{\"type\":\"result\",\"id\":{},\"outcome\":{}}}",
+            json_string(id),
+            json_string(outcome)
+        );
+    }
+
+    fn progress(&self, prog: ProgressObjects) {
+        if let Some(line) = self.progress_line(prog) {
+            println!("{}", line);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Record {
+    Line(String),
+    Info(String),
+    Start(String),
+    Result(String, String),
+}
+
+/// Reporter which keeps everything in memory, meant for tests.
+#[derive(Default)]
+pub struct Capture {
+    records: Mutex<Vec<Record>>,
+}
+
+impl Capture {
+    pub fn records(&self) -> Vec<Record> {
+        self.records.lock().unwrap().clone()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.records()
+            .into_iter()
+            .filter_map(|record| match record {
+                Record::Line(line) => Some(line),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn infos(&self) -> Vec<String> {
+        self.records()
+            .into_iter()
+            .filter_map(|record| match record {
+                Record::Info(message) => Some(message),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn results(&self) -> Vec<(String, String)> {
+        self.records()
+            .into_iter()
+            .filter_map(|record| match record {
+                Record::Result(id, outcome) => Some((id, outcome)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn push(&self, record: Record) {
+        self.records.lock().unwrap().push(record);
+    }
+}
+
+impl Reporter for Capture {
+    fn print(&self, line: &str) {
+        self.push(Record::Line(line.into()));
+    }
+
+    fn info(&self, message: &str) {
+        self.push(Record::Info(message.into()));
+    }
+
+    fn start(&self, id: &str) {
+        self.push(Record::Start(id.into()));
+    }
+
+    fn result(&self, id: &str, outcome: &str) {
+        self.push(Record::Result(id.into(), outcome.into()));
+    }
+
+    fn progress(&self, _: ProgressObjects) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_escape() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("a\"b\\c\nd"), "\"a\\\"b\\\\c\\nd\"");
+        assert_eq!(json_string("\u{1}"), "\"\\u0001\"");
+    }
+
+    #[test]
+    fn json_progress_throttled() {
+        let json = Json::default();
+        let progress = |received, indexed| ProgressObjects {
+            total: 1000,
+            received,
+            indexed,
+        };
+        assert!(json.progress_line(progress(0, 0)).is_some());
+        assert!(json.progress_line(progress(5, 0)).is_none());
+        assert_eq!(
+            json.progress_line(progress(10, 0)),
+            Some(r#"{"type":"progress","total":1000,"received":10,"indexed":0}"#.into())
+        );
+        assert!(json.progress_line(progress(19, 9)).is_none());
+        assert!(json.progress_line(progress(19, 10)).is_some());
+    }
+
+    #[test]
+    fn align() {
+        assert_eq!(
+            table(&[
+                vec!["id".into(), "branch".into()],
+                vec!["long/id".into(), "main".into()],
+            ]),
+            vec!["id       branch", "long/id  main"]
+        );
+    }
+
+    #[test]
+    fn capture() {
+        let capture = Capture::default();
+        capture.start("a");
+        capture.result("a", "OK");
+        capture.print("line");
+        capture.info("message");
+        assert_eq!(capture.lines(), vec!["line"]);
+        assert_eq!(capture.infos(), vec!["message"]);
+        assert_eq!(capture.results(), vec![("a".into(), "OK".into())]);
+    }
+
+    #[test]
+    fn names() {
+        assert!(from_name("human").is_some());
+        assert!(from_name("plain").is_some());
+        assert!(from_name("json").is_some());
+        assert!(from_name("fancy").is_none());
+    }
+}
@@ -0,0 +1,58 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Get current time in seconds since the Unix epoch.
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |dur| dur.as_secs() as i64)
+}
+
+const UNITS: [(i64, &str); 6] = [
+    (365 * 24 * 3600, "y"),
+    (30 * 24 * 3600, "mo"),
+    (7 * 24 * 3600, "w"),
+    (24 * 3600, "d"),
+    (3600, "h"),
+    (60, "m"),
+];
+
+/// Format duration in seconds using its largest unit, e.g. `3d` or `2y`.
+pub fn format_age(secs: i64) -> String {
+    UNITS
+        .iter()
+        .find(|(unit, _)| secs >= *unit)
+        .map_or("now".into(), |(unit, name)| {
+            format!("{}{}", secs / unit, name)
+        })
+}
+
+/// Parse duration such as `3d` or `2y` into seconds.
+pub fn parse_age(s: &str) -> Result<i64, String> {
+    let split = s.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(s.len());
+    let (count, unit) = s.split_at(split);
+    count
+        .parse::<i64>()
+        .ok()
+        .zip(UNITS.iter().find(|(_, name)| *name == unit))
+        .and_then(|(count, (secs, _))| count.checked_mul(*secs))
+        .ok_or_else(|| format!("invalid age '{}'", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn age() {
+        assert_eq!(format_age(0), "now");
+        assert_eq!(format_age(59), "now");
+        assert_eq!(format_age(120), "2m");
+        assert_eq!(format_age(3 * 24 * 3600 + 5), "3d");
+        assert_eq!(format_age(800 * 24 * 3600), "2y");
+        assert_eq!(parse_age("3d"), Ok(3 * 24 * 3600));
+        assert_eq!(parse_age("2mo"), Ok(60 * 24 * 3600));
+        assert!(parse_age("3").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("999999999999y").is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use crate::time::now;
+use crate::{link, repo};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+/// File in trash entry describing what was removed.
+const META: &str = "meta.toml";
+/// Directory in trash entry holding the repository.
+const REPO: &str = "repo";
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Meta {
+    /// Id of the repository, i.e. its path relative to source directory.
+    pub path: PathBuf,
+    /// Time of removal in seconds since the Unix epoch.
+    pub time: i64,
+    #[serde(default)]
+    pub links: Vec<TrashedLink>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TrashedLink {
+    /// Name of the link in bin directory.
+    pub name: String,
+    /// Linked file relative to the repository.
+    pub path: PathBuf,
+    pub mode: link::Mode,
+}
+
+/// Repository in trash.
+#[derive(Debug)]
+pub struct Entry {
+    pub dir: PathBuf,
+    pub meta: Meta,
+}
+
+impl Entry {
+    fn read(dir: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(dir.join(META))?;
+        let meta = toml::from_str(&content).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid '{}': {}", dir.join(META).display(), err),
+            )
+        })?;
+        Ok(Self {
+            dir: dir.into(),
+            meta,
+        })
+    }
+
+    /// Move repository back to source directory and recreate its links
+    /// returning count of recreated links.
+    pub fn restore(self, src: &Path, bin: &Path) -> io::Result<usize> {
+        let dest = src.join(&self.meta.path);
+        repo::rename(bin, &self.dir.join(REPO), &dest)
+            .and_then(|(links, _)| repo::retargeted(links))?;
+        let links = self
+            .meta
+            .links
+            .iter()
+            .filter(|trashed| {
+                let sym = bin.join(&trashed.name);
+                sym.symlink_metadata().is_err()
+                    && link::create_with(bin, &sym, &dest.join(&trashed.path), trashed.mode).is_ok()
+            })
+            .count();
+        fs::remove_dir_all(&self.dir)?;
+        Ok(links)
+    }
+
+    /// Delete repository permanently.
+    pub fn purge(self) -> io::Result<()> {
+        fs::remove_dir_all(&self.dir)
+    }
+}
+
+/// Move repository into trash removing its links and previous parent directories if empty.
+pub fn put(trash: &Path, src: &Path, bin: &Path, dir: &Path) -> io::Result<Entry> {
+    let links = link::iterate(bin, vec![dir.into()])
+        .map(|iter| iter.collect::<Vec<_>>())
+        .unwrap_or_default();
+    let time = now();
+    let entry_dir = (0..)
+        .map(|n| trash.join(format!("{}-{}", time, n)))
+        .find(|path| !path.exists())
+        .unwrap();
+    fs::create_dir_all(&entry_dir)?;
+
+    let meta = Meta {
+        path: dir.strip_prefix(src).unwrap_or(dir).into(),
+        time,
+        links: links
+            .iter()
+            .filter_map(|(sym, dest)| {
+                Some(TrashedLink {
+                    name: sym.file_name()?.to_string_lossy().to_string(),
+                    path: dest.strip_prefix(dir).ok()?.into(),
+                    mode: link::mode(sym)?,
+                })
+            })
+            .collect(),
+    };
+    let write_meta = toml::to_string(&meta)
+        .map_err(io::Error::other)
+        .and_then(|content| fs::write(entry_dir.join(META), content));
+    if let Err(err) = write_meta.and_then(|_| repo::move_dir(dir, &entry_dir.join(REPO))) {
+        fs::remove_dir_all(&entry_dir).ok();
+        return Err(err);
+    }
+
+    for (sym, _) in &links {
+        link::remove(sym).ok();
+    }
+    dir.parent().map(repo::remove_dir_rec);
+    Ok(Entry {
+        dir: entry_dir,
+        meta,
+    })
+}
+
+/// Get entries in trash ordered from oldest to newest.
+pub fn list(trash: &Path) -> io::Result<Vec<Entry>> {
+    let mut entries = match trash.read_dir() {
+        Ok(iter) => iter
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.join(META).is_file())
+            .map(|path| Entry::read(&path))
+            .collect::<io::Result<Vec<_>>>()?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+        Err(err) => return Err(err),
+    };
+    entries.sort_by(|a, b| (a.meta.time, &a.dir).cmp(&(b.meta.time, &b.dir)));
+    Ok(entries)
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::Home;
+use git2::Repository;
+
+#[test]
+fn adopt_moves_and_links() {
+    let home = Home::new("adopt");
+    let remote = home.remote("tool");
+    remote.commit_executable("tool", "#!/bin/sh\n");
+    let external = home.dir.join("code/tool");
+    Repository::clone(&remote.url(), &external).unwrap();
+
+    assert_eq!(
+        home.run_answering(&["adopt", external.to_str().unwrap()], &["y"]),
+        0
+    );
+    let local = home.src(&remote.id());
+    assert!(!external.exists());
+    assert!(local.join("tool").is_file());
+    assert_eq!(home.bin("tool").read_link().unwrap(), local.join("tool"));
+}
+
+#[test]
+fn adopt_in_place() {
+    let home = Home::new("adopt-in-place");
+    let remote = home.remote("tool");
+    let external = home.dir.join("code/tool");
+    Repository::clone(&remote.url(), &external).unwrap();
+
+    assert_eq!(
+        home.run(&["adopt", "-f", "--in-place", external.to_str().unwrap()]),
+        0
+    );
+    assert!(external.join("README").is_file());
+    assert_eq!(home.src(&remote.id()).read_link().unwrap(), external);
+    assert_eq!(home.run(&["status"]), 0);
+    assert_eq!(home.capture.lines().len(), 2);
+
+    assert_eq!(
+        home.run(&["adopt", "-f", "--in-place", external.to_str().unwrap()]),
+        1
+    );
+    assert!(home.capture.infos()[0].ends_with("already adopted"));
+}
+
+#[test]
+fn adopt_collision_and_missing_origin() {
+    let home = Home::new("adopt-collision");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+    let external = home.dir.join("code/tool");
+    Repository::clone(&remote.url(), &external).unwrap();
+    let bare = home.dir.join("code/bare");
+    Repository::init(&bare).unwrap();
+
+    assert_eq!(
+        home.run(&[
+            "adopt",
+            "-f",
+            external.to_str().unwrap(),
+            bare.to_str().unwrap()
+        ]),
+        2
+    );
+    assert!(external.exists());
+    assert_eq!(
+        home.capture.infos(),
+        vec![
+            format!(
+                "could not adopt '{}': '{}' already exists",
+                external.display(),
+                remote.id()
+            ),
+            format!("could not adopt '{}': no origin found", bare.display()),
+        ]
+    );
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::Home;
+use git2::{Repository, Signature};
+use std::fs;
+
+#[test]
+fn reports_local_only_work() {
+    let home = Home::new("audit");
+    let clean = home.remote("clean");
+    let busy = home.remote("busy");
+    home.run(&["clone", &clean.url(), &busy.url()]);
+    let local = home.src(&busy.id());
+    let mut repo = Repository::open(&local).unwrap();
+    {
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("feature", &head, false).unwrap();
+        repo.tag_lightweight("pushed", head.as_object(), false)
+            .unwrap();
+    }
+    common::commit(&local, &[("LOCAL", "local\n")], "local");
+    {
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.tag_lightweight("v1", head.as_object(), false).unwrap();
+    }
+    fs::write(local.join("README"), "stashed\n").unwrap();
+    repo.stash_save(&Signature::now("a", "a@b").unwrap(), "wip", None)
+        .unwrap();
+    fs::write(local.join("NEW"), "new\n").unwrap();
+
+    assert_eq!(home.run(&["audit"]), 0);
+    assert_eq!(
+        home.capture.lines(),
+        vec![format!(
+            "{}\n  branch 'feature' has no upstream\n  branch 'master' is 1 ahead\n  stash@{{0}}: On master: wip\n  untracked 'NEW'\n  tag 'v1' is not on any remote branch",
+            busy.id()
+        )]
+    );
+    assert_eq!(
+        home.capture.infos(),
+        vec!["1 of 2 repositories have local only work"]
+    );
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::Home;
+
+#[test]
+fn clone_from_bare_remote() {
+    let home = Home::new("clone-bare");
+    let remote = home.remote("tool");
+
+    assert_eq!(home.run(&["clone", &remote.url()]), 0);
+    assert_eq!(
+        home.capture.results(),
+        vec![(remote.id(), "done".to_string())]
+    );
+    assert!(home.src(&remote.id()).join("README").is_file());
+}
+
+#[test]
+fn clone_failure_cleans_up() {
+    let home = Home::new("clone-failure");
+    let url = format!("file://localhost{}", home.dir.join("missing.git").display());
+    let id = format!("local{}", home.dir.join("missing").display());
+
+    assert_eq!(home.run(&["clone", &url]), 1);
+    assert_eq!(home.capture.results(), vec![(id.clone(), "failed".into())]);
+    assert!(!home.src(&id).exists());
+    assert!(!home.config.src.exists());
+}
+
+#[test]
+fn clone_into_existing_refused() {
+    let home = Home::new("clone-existing");
+    let remote = home.remote("tool");
+    assert_eq!(home.run(&["clone", &remote.url()]), 0);
+    let local = home.src(&remote.id());
+    common::commit(&local, &[("LOCAL", "local\n")], "local");
+
+    let url = remote.url();
+    assert_eq!(home.run(&["clone", url.trim_end_matches(".git")]), 1);
+    assert_eq!(
+        home.capture.infos(),
+        vec![format!("'{}' already exists", remote.id())]
+    );
+    assert!(local.join("LOCAL").is_file());
+
+    let other = home.remote("other");
+    let url = other.url();
+    assert_eq!(home.run(&["clone", &url, url.trim_end_matches(".git")]), 1);
+    assert_eq!(home.capture.results().len(), 2);
+    assert!(home.src(&other.id()).join("README").is_file());
+}
+
+#[test]
+fn clone_many_in_parallel() {
+    let home = Home::new("clone-many");
+    let remotes = (0..4)
+        .map(|i| home.remote(&format!("tool{}", i)))
+        .collect::<Vec<_>>();
+    let urls = remotes
+        .iter()
+        .map(|remote| remote.url())
+        .collect::<Vec<_>>();
+    let mut args = vec!["clone", "-v", "--parallel", "2"];
+    args.extend(urls.iter().map(|url| url.as_str()));
+
+    assert_eq!(home.run(&args), 0, "{:?}", home.capture.records());
+    assert_eq!(home.capture.results().len(), 4);
+    for remote in remotes {
+        assert!(home.src(&remote.id()).join("README").is_file());
+    }
+}
+
+#[test]
+fn clone_and_pull_local_path() {
+    let home = Home::new("clone-path");
+    let remote = home.remote("tool");
+    let path = remote.bare.display().to_string();
+
+    assert_eq!(home.run(&["clone", &path]), 0);
+    let local = home.src(&remote.id());
+    assert!(remote.id().starts_with("local/"));
+    assert!(local.join("README").is_file());
+
+    remote.commit(&[("README", "updated\n")], "update");
+    assert_eq!(home.run(&["pull", "tool"]), 0);
+    assert_eq!(
+        std::fs::read_to_string(local.join("README")).unwrap(),
+        "updated\n"
+    );
+}
+
+#[test]
+fn clone_with_settings() {
+    let home = Home::new("clone-settings");
+    let remote = home.remote("tool");
+    let remotes = remote.bare.parent().unwrap().display().to_string();
+    std::fs::create_dir_all(&home.config.home).unwrap();
+    std::fs::write(
+        home.config.home.join("config.toml"),
+        format!(
+            "local = \"mine\"\nlocal_root = {:?}\n[shorthands]\nhere = \"file://localhost{}\"\n",
+            remotes, remotes
+        ),
+    )
+    .unwrap();
+
+    assert_eq!(home.run(&["clone", "here:tool.git"]), 0);
+    assert_eq!(
+        home.capture.results(),
+        vec![("mine/tool".to_string(), "done".to_string())]
+    );
+    assert!(home.src("mine/tool").join("README").is_file());
+    assert_eq!(home.run(&["list", "^mine/tool"]), 0);
+}
@@ -0,0 +1,158 @@
+#![allow(dead_code)]
+
+use git2::{IndexAddOption, Repository, RepositoryInitOptions, Signature};
+use osoy::prompt::Scripted;
+use osoy::report::Capture;
+use osoy::{Config, Exec, Operator};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{env, fs, process};
+use structopt::StructOpt;
+
+const BRANCH: &str = "master";
+
+/// Temporary Osoy home with a capturing reporter, removed when dropped.
+pub struct Home {
+    pub dir: PathBuf,
+    pub config: Config,
+    pub capture: Arc<Capture>,
+}
+
+impl Home {
+    pub fn new(name: &str) -> Self {
+        let dir = env::temp_dir().join(format!("osoy-test-{}-{}", process::id(), name));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        let capture = Arc::new(Capture::default());
+        Self {
+            config: Config::from_home(&dir.join("home"))
+                .with_reporter(capture.clone())
+                .with_prompter(Arc::new(Scripted::default())),
+            dir,
+            capture,
+        }
+    }
+
+    /// Run operator with arguments as if given on the command line returning its exit code.
+    pub fn run(&self, args: &[&str]) -> i32 {
+        self.run_answering(args, &[])
+    }
+
+    /// Same as `run` except prompts are answered in given order.
+    pub fn run_answering(&self, args: &[&str], answers: &[&str]) -> i32 {
+        Operator::from_iter_safe(["osoy"].iter().chain(args.iter()))
+            .unwrap()
+            .exec(
+                self.config
+                    .clone()
+                    .with_prompter(Arc::new(Scripted::new(answers.to_vec()))),
+            )
+    }
+
+    /// Create a bare remote repository with an initial commit.
+    pub fn remote(&self, name: &str) -> Remote {
+        let remote = Remote {
+            bare: self.dir.join("remotes").join(format!("{}.git", name)),
+            work: self.dir.join("work").join(name),
+        };
+        let mut options = RepositoryInitOptions::new();
+        options.initial_head(BRANCH);
+        Repository::init_opts(&remote.bare, options.bare(true)).unwrap();
+        let mut options = RepositoryInitOptions::new();
+        options.initial_head(BRANCH);
+        Repository::init_opts(&remote.work, &options)
+            .unwrap()
+            .remote("origin", remote.bare.to_str().unwrap())
+            .unwrap();
+        remote.commit(&[("README", "init\n")], "init");
+        remote
+    }
+
+    /// Path of repository in Osoy home given its id.
+    pub fn src(&self, id: &str) -> PathBuf {
+        self.config.src.join(id)
+    }
+
+    pub fn bin(&self, name: &str) -> PathBuf {
+        self.config.bin.join(name)
+    }
+}
+
+impl Drop for Home {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+/// Bare repository with a working clone used to publish commits.
+pub struct Remote {
+    pub bare: PathBuf,
+    pub work: PathBuf,
+}
+
+impl Remote {
+    pub fn url(&self) -> String {
+        format!("file://localhost{}", self.bare.display())
+    }
+
+    /// Id of the repository when cloned using `url`.
+    pub fn id(&self) -> String {
+        format!("local{}", self.bare.with_extension("").display())
+    }
+
+    /// Write files, commit and push them to the bare repository.
+    pub fn commit(&self, files: &[(&str, &str)], message: &str) {
+        commit(&self.work, files, message);
+        let repo = Repository::open(&self.work).unwrap();
+        let refspec = format!("refs/heads/{0}:refs/heads/{0}", BRANCH);
+        repo.find_remote("origin")
+            .unwrap()
+            .push(&[refspec.as_str()], None)
+            .unwrap();
+    }
+
+    /// Commit an executable file and push it to the bare repository.
+    pub fn commit_executable(&self, name: &str, content: &str) {
+        let path = self.work.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        make_executable(&path);
+        self.commit(&[], &format!("add {}", name));
+    }
+}
+
+/// Write files and commit all changes in repository.
+pub fn commit(dir: &Path, files: &[(&str, &str)], message: &str) {
+    for (name, content) in files {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    let repo = Repository::open(dir).unwrap();
+    let mut index = repo.index().unwrap();
+    index
+        .add_all(["*"].iter(), IndexAddOption::DEFAULT, None)
+        .unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("Osoy Test", "test@osoy").unwrap();
+    let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parent.iter().collect::<Vec<_>>(),
+    )
+    .unwrap();
+}
+
+pub fn make_executable(path: &Path) {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::Home;
+use git2::Repository;
+use std::fs;
+
+#[test]
+fn finds_and_fixes_problems() {
+    let home = Home::new("doctor");
+    let remote = home.remote("tool");
+    let moved = home.remote("moved");
+    remote.commit_executable("tool", "#!/bin/sh\n");
+    home.run(&["clone", &remote.url(), &moved.url()]);
+    assert_eq!(home.run(&["link", "-f", "tool"]), 0);
+
+    fs::remove_file(home.src(&remote.id()).join("tool")).unwrap();
+    std::os::unix::fs::symlink("/bin/sh", home.bin("sh")).unwrap();
+    fs::write(home.bin("stray"), "").unwrap();
+    Repository::open(home.src(&moved.id()))
+        .unwrap()
+        .remote_set_url("origin", &remote.url().replace("tool.git", "other.git"))
+        .unwrap();
+
+    assert_eq!(home.run(&["doctor"]), 4);
+    let lines = home.capture.lines();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("outside   sh"));
+    assert!(lines[1].starts_with("file      stray"));
+    assert!(lines[2].starts_with("dangling  tool"));
+    assert!(lines[3].starts_with(&format!("origin    {}", moved.id())));
+    assert!(lines[3].ends_with("remotes/other"));
+
+    assert_eq!(
+        home.run_answering(&["doctor", "--fix"], &["y", "n", "y", "y"]),
+        1
+    );
+    assert!(home.bin("tool").symlink_metadata().is_err());
+    assert!(home.bin("sh").symlink_metadata().is_ok());
+    assert!(!home.bin("stray").exists());
+    assert!(!home.src(&moved.id()).exists());
+    assert!(home
+        .src(&remote.id().replace("remotes/tool", "remotes/other"))
+        .join(".git")
+        .exists());
+
+    assert_eq!(home.run(&["doctor", "--fix", "-f"]), 0);
+    assert_eq!(home.run(&["doctor"]), 0);
+}
+
+#[test]
+fn kept_remote_not_reported() {
+    let home = Home::new("doctor-kept-remote");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+    assert_eq!(
+        home.run(&["rename", "--keep-remote", "tool", "example.com/mine/tool"]),
+        0
+    );
+
+    assert_eq!(home.run(&["doctor"]), 0);
+    assert!(home.capture.lines().is_empty());
+    assert_eq!(home.run(&["doctor", "--fix", "-f"]), 0);
+    assert!(home.src("example.com/mine/tool").join("README").is_file());
+    assert!(!home.src(&remote.id()).exists());
+
+    assert_eq!(
+        home.run(&["rename", "example.com/mine/tool", "example.com/other/tool"]),
+        0
+    );
+    assert_eq!(
+        home.run(&[
+            "rename",
+            "--keep-remote",
+            "example.com/other/tool",
+            "example.com/mine/tool"
+        ]),
+        0
+    );
+    assert_eq!(home.run(&["doctor"]), 0);
+}
@@ -0,0 +1,414 @@
+mod common;
+
+use common::Home;
+use osoy::Operator;
+use std::fs;
+use std::process::Command;
+use structopt::StructOpt;
+
+#[test]
+fn link_and_unlink() {
+    let home = Home::new("link-unlink");
+    let remote = home.remote("tool");
+    remote.commit_executable("tool", "#!/bin/sh\n");
+    home.run(&["clone", &remote.url()]);
+    let exe = home.src(&remote.id()).join("tool");
+
+    assert_eq!(home.run(&["link", "-f", "tool"]), 0);
+    assert_eq!(home.bin("tool").read_link().unwrap(), exe);
+    assert!(!home.bin("README").exists());
+
+    assert_eq!(home.run(&["list", "-E", "tool"]), 0);
+    assert_eq!(
+        home.capture.lines(),
+        vec![format!("{}\n  tool <- tool", remote.id())]
+    );
+
+    assert_eq!(home.run_answering(&["unlink", "tool"], &["y"]), 0);
+    assert!(home.bin("tool").symlink_metadata().is_err());
+}
+
+#[test]
+fn link_prompt_declined() {
+    let home = Home::new("link-declined");
+    let remote = home.remote("tool");
+    remote.commit_executable("tool", "#!/bin/sh\n");
+    home.run(&["clone", &remote.url()]);
+
+    assert_eq!(home.run_answering(&["link", "tool"], &["n"]), 0);
+    assert!(home.bin("tool").symlink_metadata().is_err());
+
+    assert_eq!(home.run_answering(&["link", "tool"], &["yes"]), 0);
+    assert!(home.bin("tool").symlink_metadata().is_ok());
+}
+
+#[test]
+fn already_linked_is_skipped() {
+    let home = Home::new("link-skip");
+    let remote = home.remote("tool");
+    remote.commit_executable("tool", "#!/bin/sh\n");
+    home.run(&["clone", &remote.url()]);
+
+    assert_eq!(home.run(&["link", "-f", "tool"]), 0);
+    assert_eq!(home.run(&["link", "-f", "tool"]), 0);
+    assert!(home.capture.infos().is_empty());
+}
+
+#[test]
+fn broken_symlink_is_unlinked() {
+    let home = Home::new("link-broken");
+    let remote = home.remote("tool");
+    remote.commit_executable("tool", "#!/bin/sh\n");
+    home.run(&["clone", &remote.url()]);
+    home.run(&["link", "-f", "tool"]);
+    fs::remove_file(home.src(&remote.id()).join("tool")).unwrap();
+
+    assert!(!home.bin("tool").exists());
+    assert_eq!(home.run(&["unlink", "-f", "tool"]), 0);
+    assert!(home.bin("tool").symlink_metadata().is_err());
+}
+
+#[test]
+fn manifest_overrides_heuristic() {
+    let home = Home::new("link-manifest");
+    let remote = home.remote("tool");
+    remote.commit_executable("configure", "#!/bin/sh\n");
+    remote.commit_executable("bin/tool", "#!/bin/sh\n");
+    remote.commit(
+        &[(
+            ".osoy.toml",
+            "[[link]]\npath = \"bin/tool\"\n\n[[link]]\npath = \"target/release/app\"\nname = \"tool-app\"\n",
+        )],
+        "manifest",
+    );
+    home.run(&["clone", &remote.url()]);
+    let local = home.src(&remote.id());
+
+    assert_eq!(home.run(&["link", "-f", "tool"]), 0);
+    assert_eq!(
+        home.bin("tool").read_link().unwrap(),
+        local.join("bin/tool")
+    );
+    assert!(home.bin("configure").symlink_metadata().is_err());
+    assert!(home.bin("tool-app").symlink_metadata().is_err());
+    assert_eq!(home.capture.infos().len(), 1);
+
+    fs::create_dir_all(local.join("target/release")).unwrap();
+    fs::write(local.join("target/release/app"), "").unwrap();
+    common::make_executable(&local.join("target/release/app"));
+    assert_eq!(home.run(&["link", "-f", "tool"]), 0);
+    assert_eq!(
+        home.bin("tool-app").read_link().unwrap(),
+        local.join("target/release/app")
+    );
+
+    assert_eq!(home.run(&["list", "-E", "tool"]), 0);
+    assert_eq!(
+        home.capture.lines(),
+        vec![format!(
+            "{}\n  bin/tool <- tool\n  target/release/app <- tool-app",
+            remote.id()
+        )]
+    );
+}
+
+#[test]
+fn manifest_outside_repository_rejected() {
+    let home = Home::new("link-manifest-outside");
+    let remote = home.remote("tool");
+    remote.commit(
+        &[(".osoy.toml", "[[link]]\npath = \"../escape\"\n")],
+        "manifest",
+    );
+    home.run(&["clone", &remote.url()]);
+
+    assert_eq!(home.run(&["link", "-f", "tool"]), 1);
+    assert!(home.config.bin.read_dir().is_err());
+}
+
+#[test]
+fn subdirectory_glob_and_rename() {
+    let home = Home::new("link-select");
+    let remote = home.remote("tool");
+    remote.commit_executable("configure", "#!/bin/sh\n");
+    remote.commit_executable("bin/tool", "#!/bin/sh\n");
+    remote.commit(
+        &[("scripts/a.sh", "echo a\n"), ("scripts/b.sh", "echo b\n")],
+        "scripts",
+    );
+    home.run(&["clone", &remote.url()]);
+    let local = home.src(&remote.id());
+
+    assert_eq!(home.run(&["link", "-f", "--path", "bin", "tool"]), 0);
+    assert_eq!(
+        home.bin("tool").read_link().unwrap(),
+        local.join("bin/tool")
+    );
+    assert!(home.bin("configure").symlink_metadata().is_err());
+
+    assert_eq!(
+        home.run(&["link", "-f", "--glob", "scripts/*.sh", "tool"]),
+        0
+    );
+    assert!(home.bin("a.sh").symlink_metadata().is_ok());
+    assert!(home.bin("b.sh").symlink_metadata().is_ok());
+
+    assert_eq!(
+        home.run(&["link", "-f", "--glob", "scripts/*.sh", "--as", "x", "tool"]),
+        1
+    );
+    assert_eq!(
+        home.run(&[
+            "link",
+            "-f",
+            "--glob",
+            "scripts/a.sh",
+            "--as",
+            "tool-a",
+            "tool"
+        ]),
+        0
+    );
+    assert_eq!(
+        home.bin("tool-a").read_link().unwrap(),
+        local.join("scripts/a.sh")
+    );
+
+    assert_eq!(home.run(&["link", "-f", "--path", "../..", "tool"]), 1);
+}
+
+#[test]
+fn list_links_outside_root() {
+    let home = Home::new("link-list-select");
+    let remote = home.remote("tool");
+    remote.commit_executable("target/release/app", "#!/bin/sh\n");
+    home.run(&["clone", &remote.url()]);
+
+    assert_eq!(home.run(&["list", "--where", "exe"]), 1);
+    assert!(home.capture.lines().is_empty());
+
+    assert_eq!(
+        home.run(&["link", "-f", "--glob", "target/release/*", "tool"]),
+        0
+    );
+    assert_eq!(
+        home.run(&[
+            "link",
+            "-f",
+            "--glob",
+            "target/release/app",
+            "--as",
+            "tool-app",
+            "tool"
+        ]),
+        0
+    );
+    assert_eq!(home.run(&["list", "-E", "tool"]), 0);
+    assert_eq!(home.run(&["list", "--where", "exe"]), 0);
+    assert_eq!(
+        home.capture.lines(),
+        vec![
+            format!("{}\n  target/release/app <- app, tool-app", remote.id()),
+            remote.id()
+        ]
+    );
+}
+
+#[test]
+fn conflict_between_repositories() {
+    let home = Home::new("link-conflict");
+    let alpha = home.remote("alpha");
+    let beta = home.remote("beta");
+    alpha.commit_executable("tool", "#!/bin/sh\n");
+    beta.commit_executable("tool", "#!/bin/sh\n");
+    home.run(&["clone", &alpha.url(), &beta.url()]);
+
+    assert_eq!(home.run(&["link", "-f", "alpha", "beta"]), 1);
+    assert!(home.bin("tool").symlink_metadata().is_err());
+    let conflict = home.capture.infos();
+    assert_eq!(conflict.len(), 1);
+    assert!(conflict[0].starts_with("'tool' is provided by "));
+    assert!(conflict[0].contains(&alpha.id()) && conflict[0].contains(&beta.id()));
+
+    assert_eq!(home.run(&["link", "-f", "--prefix", "alpha", "beta"]), 0);
+    assert!(home.bin("tool").symlink_metadata().is_err());
+    assert_eq!(
+        home.bin("alpha-tool").read_link().unwrap(),
+        home.src(&alpha.id()).join("tool")
+    );
+    assert_eq!(
+        home.bin("beta-tool").read_link().unwrap(),
+        home.src(&beta.id()).join("tool")
+    );
+}
+
+#[test]
+fn conflict_with_existing_link() {
+    let home = Home::new("link-conflict-existing");
+    let alpha = home.remote("alpha");
+    let beta = home.remote("beta");
+    alpha.commit_executable("tool", "#!/bin/sh\n");
+    beta.commit_executable("tool", "#!/bin/sh\n");
+    home.run(&["clone", &alpha.url(), &beta.url()]);
+    let alpha_tool = home.src(&alpha.id()).join("tool");
+    let beta_tool = home.src(&beta.id()).join("tool");
+
+    assert_eq!(home.run(&["link", "-f", "alpha"]), 0);
+    assert_eq!(home.run(&["link", "-f", "beta"]), 1);
+    assert_eq!(
+        home.capture.infos(),
+        vec![format!(
+            "'tool' is provided by {} and {}",
+            beta.id(),
+            alpha.id()
+        )]
+    );
+    assert_eq!(home.bin("tool").read_link().unwrap(), alpha_tool);
+
+    assert_eq!(home.run(&["link", "-f", "--replace", "beta"]), 0);
+    assert_eq!(home.bin("tool").read_link().unwrap(), beta_tool);
+
+    assert_eq!(home.run(&["link", "-f", "alpha"]), 0);
+    assert_eq!(home.bin("tool").read_link().unwrap(), beta_tool);
+}
+
+#[test]
+fn conflict_choice_is_recorded() {
+    let home = Home::new("link-conflict-choice");
+    let alpha = home.remote("alpha");
+    let beta = home.remote("beta");
+    alpha.commit_executable("tool", "#!/bin/sh\n");
+    beta.commit_executable("tool", "#!/bin/sh\n");
+    home.run(&["clone", &alpha.url(), &beta.url()]);
+
+    assert_eq!(home.run_answering(&["link", "alpha", "beta"], &["2"]), 0);
+    assert_eq!(
+        home.bin("tool").read_link().unwrap(),
+        home.src(&beta.id()).join("tool")
+    );
+    assert!(fs::read_to_string(home.config.home.join("links.toml"))
+        .unwrap()
+        .contains(&beta.id()));
+
+    assert_eq!(home.run_answering(&["link", "alpha"], &[]), 0);
+    assert_eq!(
+        home.bin("tool").read_link().unwrap(),
+        home.src(&beta.id()).join("tool")
+    );
+}
+
+#[test]
+fn moved_file_is_relinked() {
+    let home = Home::new("link-moved");
+    let remote = home.remote("tool");
+    remote.commit_executable("tool", "#!/bin/sh\n");
+    remote.commit(&[(".osoy.toml", "[[link]]\npath = \"tool\"\n")], "manifest");
+    home.run(&["clone", &remote.url()]);
+    let local = home.src(&remote.id());
+    assert_eq!(home.run(&["link", "-f", "tool"]), 0);
+
+    fs::create_dir_all(local.join("bin")).unwrap();
+    fs::copy(local.join("tool"), local.join("bin/tool")).unwrap();
+    fs::write(local.join(".osoy.toml"), "[[link]]\npath = \"bin/tool\"\n").unwrap();
+    assert_eq!(home.run(&["link", "-f", "tool"]), 0);
+    assert_eq!(
+        home.bin("tool").read_link().unwrap(),
+        local.join("bin/tool")
+    );
+}
+
+#[test]
+fn link_modes() {
+    let home = Home::new("link-modes");
+    let remote = home.remote("tool");
+    remote.commit_executable("tool", "#!/bin/sh\npwd\n");
+    home.run(&["clone", &remote.url()]);
+    let local = home.src(&remote.id());
+    let exe = local.join("tool");
+
+    for mode in &["absolute", "relative", "hard", "copy", "wrapper"] {
+        assert_eq!(home.run(&["link", "-f", "--mode", mode, "tool"]), 0);
+        assert_eq!(
+            osoy::link::target(&home.config.bin, &home.bin("tool")),
+            Some(exe.clone())
+        );
+        assert_eq!(
+            osoy::link::deref_rec(&home.config.bin, &home.bin("tool")),
+            exe
+        );
+        assert_eq!(
+            osoy::link::mode(&home.bin("tool")).unwrap(),
+            mode.parse().unwrap()
+        );
+        assert_eq!(home.run(&["link", "-f", "tool"]), 0);
+
+        assert_eq!(home.run(&["list", "-E", "tool"]), 0);
+        assert_eq!(
+            home.capture.lines().last().unwrap(),
+            &format!("{}\n  tool <- tool", remote.id())
+        );
+
+        let output = Command::new(home.bin("tool")).output().unwrap();
+        assert!(output.status.success());
+        if *mode == "wrapper" {
+            assert_eq!(output.stdout, format!("{}\n", local.display()).as_bytes());
+        }
+
+        assert_eq!(home.run(&["unlink", "-f", "tool"]), 0);
+        assert!(home.bin("tool").symlink_metadata().is_err());
+        assert!(!home.bin(osoy::link::INDEX).exists());
+    }
+}
+
+#[test]
+fn link_mode_kept_on_rename() {
+    let home = Home::new("link-mode-rename");
+    let remote = home.remote("tool");
+    remote.commit_executable("tool", "#!/bin/sh\n");
+    home.run(&["clone", &remote.url()]);
+    assert_eq!(home.run(&["link", "-f", "--mode", "relative", "tool"]), 0);
+    assert!(home.bin("tool").read_link().unwrap().is_relative());
+
+    assert_eq!(home.run(&["rename", "tool", "example.com/a/tool"]), 0);
+    assert!(home.bin("tool").read_link().unwrap().is_relative());
+    assert_eq!(
+        osoy::link::deref_rec(&home.config.bin, &home.bin("tool")),
+        home.src("example.com/a/tool").join("tool")
+    );
+}
+
+#[test]
+fn repository_index_ignored() {
+    let home = Home::new("link-repo-index");
+    let remote = home.remote("tool");
+    remote.commit(
+        &[
+            ("tool", "not executable\n"),
+            (
+                osoy::link::INDEX,
+                "[tool]\npath = \"/bin/sh\"\nmode = \"hard\"\n",
+            ),
+        ],
+        "index",
+    );
+    home.run(&["clone", &remote.url()]);
+    let local = home.src(&remote.id());
+
+    assert_eq!(
+        osoy::link::target(&home.config.bin, &local.join("tool")),
+        None
+    );
+    assert_eq!(
+        osoy::link::executables(&local).unwrap().collect::<Vec<_>>(),
+        Vec::<std::path::PathBuf>::new()
+    );
+}
+
+#[test]
+fn link_help() {
+    let err = Operator::from_iter_safe(&["osoy", "link", "--help"]).unwrap_err();
+    assert!(err
+        .message
+        .contains("Create symbolic links for executables"));
+    assert!(err.message.contains("--mode <mode>"));
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::Home;
+use std::fs;
+
+#[test]
+fn fast_forward_and_up_to_date() {
+    let home = Home::new("pull-ff");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+    remote.commit(&[("README", "updated\n")], "update");
+
+    assert_eq!(home.run(&["pull", "tool"]), 0);
+    assert_eq!(
+        fs::read_to_string(home.src(&remote.id()).join("README")).unwrap(),
+        "updated\n"
+    );
+
+    assert_eq!(home.run(&["pull", "tool"]), 0);
+    assert_eq!(
+        home.capture.results()[1..],
+        [
+            (remote.id(), "done".to_string()),
+            (remote.id(), "up-to-date".to_string())
+        ]
+    );
+}
+
+#[test]
+fn divergent_history_requires_force() {
+    let home = Home::new("pull-divergent");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+    let local = home.src(&remote.id());
+    common::commit(&local, &[("LOCAL", "local\n")], "local");
+    remote.commit(&[("README", "remote\n")], "remote");
+
+    assert_eq!(home.run(&["pull", "tool"]), 1);
+    assert_eq!(home.capture.results()[1], (remote.id(), "failed".into()));
+    assert_eq!(fs::read_to_string(local.join("README")).unwrap(), "init\n");
+
+    assert_eq!(home.run(&["pull", "--force", "tool"]), 0);
+    assert_eq!(
+        fs::read_to_string(local.join("README")).unwrap(),
+        "remote\n"
+    );
+}
+
+#[test]
+fn dirty_tree_is_kept() {
+    let home = Home::new("pull-dirty");
+    let remote = home.remote("tool");
+    remote.commit(&[("NOTES", "notes\n")], "notes");
+    home.run(&["clone", &remote.url()]);
+    let local = home.src(&remote.id());
+    fs::write(local.join("NOTES"), "uncommitted\n").unwrap();
+    fs::write(local.join("UNTRACKED"), "untracked\n").unwrap();
+    remote.commit(&[("README", "updated\n")], "update");
+
+    assert_eq!(home.run(&["pull", "tool"]), 0);
+    assert_eq!(
+        fs::read_to_string(local.join("README")).unwrap(),
+        "updated\n"
+    );
+    assert_eq!(
+        fs::read_to_string(local.join("NOTES")).unwrap(),
+        "uncommitted\n"
+    );
+    assert!(local.join("UNTRACKED").is_file());
+}
+
+#[test]
+fn dirty_tree_conflict_fails() {
+    let home = Home::new("pull-dirty-conflict");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+    let local = home.src(&remote.id());
+    fs::write(local.join("README"), "uncommitted\n").unwrap();
+    remote.commit(&[("README", "updated\n")], "update");
+
+    assert_eq!(home.run(&["pull", "tool"]), 1);
+    assert_eq!(
+        fs::read_to_string(local.join("README")).unwrap(),
+        "uncommitted\n"
+    );
+}
+
+#[test]
+fn no_matching_repositories() {
+    let home = Home::new("pull-none");
+
+    assert_eq!(home.run(&["pull", "nothing"]), 1);
+    assert_eq!(home.capture.infos().len(), 1);
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::Home;
+use git2::Repository;
+
+fn head(path: &std::path::Path) -> git2::Oid {
+    Repository::open(path)
+        .unwrap()
+        .refname_to_id("refs/heads/master")
+        .unwrap()
+}
+
+#[test]
+fn dry_run_and_push() {
+    let home = Home::new("push");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+    let local = home.src(&remote.id());
+
+    assert_eq!(home.run(&["push", "tool"]), 0);
+    common::commit(&local, &[("LOCAL", "local\n")], "local");
+
+    assert_eq!(home.run(&["push", "--dry-run", "tool"]), 0);
+    assert_ne!(head(&remote.bare), head(&local));
+
+    assert_eq!(home.run(&["push", "tool"]), 0);
+    assert_eq!(head(&remote.bare), head(&local));
+    assert_eq!(
+        home.capture.results()[1..],
+        [
+            (remote.id(), "up-to-date".to_string()),
+            (remote.id(), "would push master (1 ahead)".to_string()),
+            (remote.id(), "pushed".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn non_fast_forward_rejected() {
+    let home = Home::new("push-rejected");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+    let local = home.src(&remote.id());
+    common::commit(&local, &[("LOCAL", "local\n")], "local");
+    remote.commit(&[("README", "remote\n")], "remote");
+
+    assert_eq!(home.run(&["push", "tool"]), 1);
+    assert_eq!(
+        home.capture.results()[1],
+        (remote.id(), "rejected non-fast-forward".into())
+    );
+}
+
+#[test]
+fn all_branches_with_upstream() {
+    let home = Home::new("push-all");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+    let local = home.src(&remote.id());
+    {
+        let repo = Repository::open(&local).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("feature", &head, false).unwrap();
+    }
+    common::commit(&local, &[("LOCAL", "local\n")], "local");
+
+    assert_eq!(home.run(&["push", "--all", "tool"]), 0);
+    assert_eq!(head(&remote.bare), head(&local));
+    assert!(Repository::open(&remote.bare)
+        .unwrap()
+        .find_reference("refs/heads/feature")
+        .is_err());
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::Home;
+use git2::Repository;
+use std::fs;
+
+#[test]
+fn remove_repository_and_links() {
+    let home = Home::new("remove");
+    let remote = home.remote("tool");
+    remote.commit_executable("tool", "#!/bin/sh\n");
+    home.run(&["clone", &remote.url()]);
+    home.run(&["link", "-f", "tool"]);
+
+    assert_eq!(home.run_answering(&["remove", "tool"], &["y"]), 0);
+    assert!(!home.src(&remote.id()).exists());
+    assert!(home.bin("tool").symlink_metadata().is_err());
+    assert!(!home.config.src.exists());
+}
+
+#[test]
+fn remove_prompt_declined() {
+    let home = Home::new("remove-declined");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+
+    assert_eq!(home.run_answering(&["remove", "tool"], &["n"]), 0);
+    assert!(home.src(&remote.id()).exists());
+}
+
+#[test]
+fn remove_only_matching() {
+    let home = Home::new("remove-matching");
+    let first = home.remote("first");
+    let second = home.remote("second");
+    assert_eq!(
+        home.run(&["clone", "-v", &first.url(), &second.url()]),
+        0,
+        "{:?}",
+        home.capture.records()
+    );
+
+    assert_eq!(home.run(&["remove", "-f", "first"]), 0);
+    assert!(!home.src(&first.id()).exists());
+    assert!(home.src(&second.id()).exists());
+}
+
+#[test]
+fn remove_to_trash_and_restore() {
+    let home = Home::new("remove-restore");
+    let remote = home.remote("tool");
+    remote.commit_executable("tool", "#!/bin/sh\n");
+    home.run(&["clone", &remote.url()]);
+    home.run(&["link", "-f", "--mode", "relative", "tool"]);
+    let local = home.src(&remote.id());
+    common::commit(&local, &[("LOCAL", "local\n")], "local");
+
+    assert_eq!(home.run(&["remove", "-f", "tool"]), 1);
+    assert!(local.exists());
+    assert_eq!(home.run(&["remove", "-f", "--discard-unpushed", "tool"]), 0);
+    assert!(!local.exists());
+    assert!(home.bin("tool").symlink_metadata().is_err());
+    assert!(home.capture.infos()[1].starts_with(&format!(
+        "'{}' has local only work\n  branch 'master' is 1 ahead",
+        remote.id()
+    )));
+
+    assert_eq!(home.run(&["restore"]), 0);
+    assert_eq!(
+        home.capture.lines(),
+        vec![format!("{}  now  1 links", remote.id())]
+    );
+
+    assert_eq!(home.run(&["restore", "-f", "tool"]), 0);
+    assert!(local.join("LOCAL").is_file());
+    assert!(home.bin("tool").read_link().unwrap().is_relative());
+    assert_eq!(
+        osoy::link::deref_rec(&home.config.bin, &home.bin("tool")),
+        local.join("tool")
+    );
+    assert_eq!(home.config.trash.read_dir().unwrap().count(), 0);
+
+    assert_eq!(home.run(&["restore", "-f", "tool"]), 1);
+}
+
+#[test]
+fn purge() {
+    let home = Home::new("remove-purge");
+    let first = home.remote("first");
+    let second = home.remote("second");
+    home.run(&["clone", &first.url(), &second.url()]);
+
+    assert_eq!(home.run(&["remove", "-f", "--purge", "first"]), 0);
+    assert!(!home.config.trash.exists());
+
+    assert_eq!(home.run(&["remove", "-f", "second"]), 0);
+    assert_eq!(
+        home.run(&["restore", "-f", "--purge", "--older-than", "1d"]),
+        0
+    );
+    assert_eq!(home.config.trash.read_dir().unwrap().count(), 1);
+    assert_eq!(home.run(&["restore", "-f", "--purge"]), 0);
+    assert_eq!(home.config.trash.read_dir().unwrap().count(), 0);
+}
+
+#[test]
+fn remove_refuses_local_only_work() {
+    let home = Home::new("remove-refuse");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+    let local = home.src(&remote.id());
+    assert_eq!(
+        osoy::repo::removal_risks(&local).unwrap(),
+        Vec::<String>::new()
+    );
+
+    fs::write(local.join("README"), "changed\n").unwrap();
+    {
+        let repo = Repository::open(&local).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("feature", &head, false).unwrap();
+    }
+    assert_eq!(
+        osoy::repo::removal_risks(&local).unwrap(),
+        vec!["1 unstaged changes", "branch 'feature' has no upstream"]
+    );
+
+    assert_eq!(home.run(&["remove", "-f", "tool"]), 1);
+    assert!(local.exists());
+    assert_eq!(
+        home.capture.infos(),
+        vec![format!(
+            "'{}' has local only work\n  1 unstaged changes\n  branch 'feature' has no upstream\nrefusing to remove without --discard-unpushed",
+            remote.id()
+        )]
+    );
+}
@@ -0,0 +1,232 @@
+mod common;
+
+use common::Home;
+use git2::Repository;
+
+#[test]
+fn rename_moves_and_sets_origin() {
+    let home = Home::new("rename");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+
+    assert_eq!(home.run(&["rename", "tool", "example.com/someone/tool"]), 0);
+    let dest = home.src("example.com/someone/tool");
+    assert!(dest.join("README").is_file());
+    assert!(!home.src(&remote.id()).exists());
+    assert_eq!(
+        Repository::open(&dest)
+            .unwrap()
+            .find_remote("origin")
+            .unwrap()
+            .url(),
+        Some("https://example.com/someone/tool")
+    );
+}
+
+#[test]
+fn rename_onto_existing_fails() {
+    let home = Home::new("rename-existing");
+    let first = home.remote("first");
+    let second = home.remote("second");
+    assert_eq!(
+        home.run(&["clone", "-v", &first.url(), &second.url()]),
+        0,
+        "{:?}",
+        home.capture.records()
+    );
+
+    assert_eq!(home.run(&["rename", "first", &second.id()]), 1);
+    assert!(home.src(&first.id()).exists());
+}
+
+#[test]
+fn rename_ambiguous_target_fails() {
+    let home = Home::new("rename-ambiguous");
+    let first = home.remote("first");
+    let second = home.remote("second");
+    assert_eq!(
+        home.run(&["clone", "-v", &first.url(), &second.url()]),
+        0,
+        "{:?}",
+        home.capture.records()
+    );
+
+    assert_eq!(home.run(&["rename", "-r", ".*", "example.com/a/b"]), 1);
+    assert!(!home.src("example.com/a/b").exists());
+}
+
+#[test]
+fn rename_protocol_only() {
+    let home = Home::new("rename-protocol");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+    let path = remote.bare.display().to_string();
+
+    assert_eq!(home.run(&["rename", "tool", &path]), 0);
+    assert_eq!(
+        Repository::open(home.src(&remote.id()))
+            .unwrap()
+            .find_remote("origin")
+            .unwrap()
+            .url(),
+        Some(format!("file://{}", path).as_str())
+    );
+}
+
+#[test]
+fn rename_updates_links() {
+    let home = Home::new("rename-links");
+    let remote = home.remote("tool");
+    remote.commit_executable("tool", "#!/bin/sh\n");
+    home.run(&["clone", &remote.url()]);
+    assert_eq!(home.run(&["link", "-f", "tool"]), 0);
+
+    assert_eq!(home.run(&["rename", "tool", "example.com/someone/tool"]), 0);
+    assert_eq!(
+        home.bin("tool").read_link().unwrap(),
+        home.src("example.com/someone/tool").join("tool")
+    );
+    assert!(home.capture.infos().contains(&"updated 1 links".into()));
+    assert_eq!(home.config.bin.read_dir().unwrap().count(), 1);
+}
+
+#[test]
+fn rename_reports_links_not_updated() {
+    let home = Home::new("rename-links-failed");
+    let remote = home.remote("tool");
+    remote.commit_executable("tool", "#!/bin/sh\n");
+    home.run(&["clone", &remote.url()]);
+    assert_eq!(home.run(&["link", "-f", "tool"]), 0);
+    // Directory in place of the temporary link makes rewriting the link fail.
+    std::fs::create_dir_all(home.bin(".tool.tmp").join("blocked")).unwrap();
+
+    assert_eq!(home.run(&["rename", "tool", "example.com/someone/tool"]), 1);
+    assert!(home.src("example.com/someone/tool").exists());
+    assert!(home
+        .capture
+        .infos()
+        .iter()
+        .any(|info| info.starts_with("could not update link 'tool'")));
+}
+
+fn origin(path: &std::path::Path) -> String {
+    Repository::open(path)
+        .unwrap()
+        .find_remote("origin")
+        .unwrap()
+        .url()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn rename_keep_remote_and_remote_only() {
+    let home = Home::new("rename-modes");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+
+    assert_eq!(
+        home.run(&["rename", "--keep-remote", "tool", "example.com/a/tool"]),
+        0
+    );
+    let moved = home.src("example.com/a/tool");
+    assert_eq!(origin(&moved), remote.url());
+
+    assert_eq!(
+        home.run(&["rename", "--remote-only", "tool", "example.com/b/tool"]),
+        0
+    );
+    assert!(moved.exists());
+    assert_eq!(origin(&moved), "https://example.com/b/tool");
+}
+
+#[test]
+fn rename_batch_with_dry_run_and_undo() {
+    let home = Home::new("rename-batch");
+    let alpha = home.remote("alpha");
+    let beta = home.remote("beta");
+    home.run(&["clone", &alpha.url(), &beta.url()]);
+    let args = ["rename", "-r", r"remotes/(.*)", "example.com/neworg/$1"];
+
+    assert_eq!(home.run(&[&args[..], &["-n"]].concat()), 0);
+    let mut lines = home.capture.lines();
+    lines.sort();
+    assert_eq!(
+        lines,
+        vec![
+            format!(
+                "{} -> example.com/neworg/alpha (origin https://example.com/neworg/alpha)",
+                alpha.id()
+            ),
+            format!(
+                "{} -> example.com/neworg/beta (origin https://example.com/neworg/beta)",
+                beta.id()
+            ),
+        ]
+    );
+    assert!(home.src(&alpha.id()).exists());
+
+    assert_eq!(home.run(&args), 0);
+    assert!(!home.src(&alpha.id()).exists());
+    assert_eq!(
+        origin(&home.src("example.com/neworg/beta")),
+        "https://example.com/neworg/beta"
+    );
+
+    assert_eq!(home.run(&["rename", "--undo"]), 0);
+    assert_eq!(origin(&home.src(&alpha.id())), alpha.url());
+    assert_eq!(origin(&home.src(&beta.id())), beta.url());
+    assert!(!home.src("example.com").exists());
+
+    assert_eq!(home.run(&["rename", "--undo"]), 1);
+}
+
+#[test]
+fn rename_undo_partial() {
+    let home = Home::new("rename-undo-partial");
+    let alpha = home.remote("alpha");
+    let beta = home.remote("beta");
+    home.run(&["clone", &alpha.url(), &beta.url()]);
+    assert_eq!(
+        home.run(&["rename", "-r", r"remotes/(.*)", "example.com/neworg/$1"]),
+        0
+    );
+
+    let moved = home.src("example.com/neworg/beta");
+    let aside = home.dir.join("beta-aside");
+    std::fs::rename(&moved, &aside).unwrap();
+    assert_eq!(home.run(&["rename", "--undo"]), 1);
+    assert_eq!(origin(&home.src(&alpha.id())), alpha.url());
+
+    std::fs::create_dir_all(moved.parent().unwrap()).unwrap();
+    std::fs::rename(&aside, &moved).unwrap();
+    assert_eq!(home.run(&["rename", "--undo"]), 0);
+    assert_eq!(origin(&home.src(&beta.id())), beta.url());
+    assert_eq!(home.run(&["rename", "--undo"]), 1);
+}
+
+#[test]
+fn rename_fuzzy_requires_confirmation() {
+    let home = Home::new("rename-fuzzy");
+    let widget = home.remote("widget");
+    let other = home.remote("wxixdxgxext");
+    home.run(&["clone", &widget.url(), &other.url()]);
+
+    assert_eq!(
+        home.run(&["rename", "-z", "wdg", "example.com/a/widget"]),
+        1
+    );
+    assert!(home.src(&widget.id()).exists());
+
+    assert_eq!(
+        home.run_answering(&["rename", "-z", "wdg", "example.com/a/widget"], &["y"]),
+        0
+    );
+    assert!(home.src("example.com/a/widget").exists());
+
+    assert_eq!(
+        home.run(&["rename", "-z", "wxixdxgxext", "example.com/a/other"]),
+        0
+    );
+    assert!(home.src("example.com/a/other").exists());
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use common::Home;
+use git2::Repository;
+use osoy::Operator;
+use std::fs;
+use structopt::clap::ErrorKind;
+
+#[test]
+fn new_sets_origin() {
+    let home = Home::new("new");
+
+    assert_eq!(home.run(&["new", "example.com/someone/tool"]), 0);
+    let repo = Repository::open(home.src("example.com/someone/tool")).unwrap();
+    assert_eq!(
+        repo.find_remote("origin").unwrap().url(),
+        Some("https://example.com/someone/tool")
+    );
+
+    assert_eq!(home.run(&["new", "example.com/someone/tool"]), 0);
+    assert_eq!(home.capture.infos().len(), 1);
+}
+
+#[test]
+fn locate_unique() {
+    let home = Home::new("locate");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+
+    assert_eq!(home.run(&["locate", "tool"]), 0);
+    assert_eq!(
+        home.capture.lines(),
+        vec![home.src(&remote.id()).display().to_string()]
+    );
+    assert_eq!(home.run(&["locate", "missing"]), 1);
+}
+
+#[test]
+fn locate_nested_and_anchored() {
+    let home = Home::new("locate-nested");
+    home.run(&[
+        "new",
+        "gitlab.com/group/sub/project",
+        "gitlab.com/other/project",
+    ]);
+    let nested = home
+        .src("gitlab.com/group/sub/project")
+        .display()
+        .to_string();
+
+    assert_eq!(home.run(&["locate", "sub/project"]), 0);
+    assert_eq!(home.capture.lines(), vec![nested.clone()]);
+    assert_eq!(home.run(&["locate", "project"]), 1);
+    assert_eq!(home.run(&["locate", "^sub/project"]), 1);
+    assert_eq!(home.run(&["locate", "^gitlab.com/group/sub/project"]), 0);
+    assert_eq!(home.capture.lines(), vec![nested.clone(), nested]);
+}
+
+#[test]
+fn locate_glob_and_fuzzy() {
+    let home = Home::new("locate-fuzzy");
+    home.run(&[
+        "new",
+        "example.com/osoy/osoy",
+        "example.com/osoy/ossify",
+        "example.com/other/tool",
+    ]);
+
+    assert_eq!(home.run(&["locate", "^example.com/*/t??l"]), 0);
+    assert_eq!(home.run(&["locate", "{osoy,other}/**/*y"]), 1);
+    assert_eq!(
+        home.capture.infos(),
+        vec!["multiple entities match query\n  example.com/osoy/osoy\n  example.com/osoy/ossify"]
+    );
+
+    assert_eq!(home.run(&["locate", "-r", "os(oy"]), 1);
+    assert!(home.capture.infos()[1].starts_with("invalid regular expression 'os(oy': "));
+    let err = Operator::from_iter_checked(&["osoy", "locate", "-r", "os(oy"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ValueValidation);
+    assert!(err
+        .message
+        .contains("Invalid value for '<target>': invalid regular expression 'os(oy': "));
+    assert!(Operator::from_iter_checked(&["osoy", "locate", "os(oy"]).is_ok());
+
+    assert_eq!(home.run(&["locate", "-z", "osy"]), 0);
+    assert_eq!(home.run(&["locate", "-z", "xyz"]), 1);
+    assert_eq!(
+        home.capture.lines(),
+        vec![
+            home.src("example.com/other/tool").display().to_string(),
+            home.src("example.com/osoy/osoy").display().to_string(),
+        ]
+    );
+}
+
+#[test]
+fn pick_among_ambiguous() {
+    let home = Home::new("pick");
+    home.run(&[
+        "new",
+        "example.com/a/tool",
+        "example.com/b/tool",
+        "example.com/c/tool",
+    ]);
+
+    assert_eq!(home.run_answering(&["locate", "tool"], &["2"]), 0);
+    assert_eq!(
+        home.capture.lines(),
+        vec![home.src("example.com/b/tool").display().to_string()]
+    );
+    assert_eq!(home.run_answering(&["locate", "tool"], &[]), 1);
+    assert!(home.capture.infos()[0].starts_with("multiple entities match query"));
+
+    assert_eq!(
+        home.run_answering(&["execute", "-p", "tool", "true"], &["1 3"]),
+        0
+    );
+    assert_eq!(
+        home.capture.results(),
+        vec![
+            ("example.com/a/tool".to_string(), "OK".to_string()),
+            ("example.com/c/tool".to_string(), "OK".to_string()),
+        ]
+    );
+    assert_eq!(
+        home.run_answering(&["execute", "-p", "tool", "true"], &[]),
+        1
+    );
+}
+
+#[test]
+fn select_by_tag_and_exclude() {
+    let home = Home::new("select-tags");
+    home.run(&[
+        "new",
+        "example.com/work/api",
+        "example.com/work/legacy-api",
+        "example.com/personal/dots",
+        "example.com/personal/tool",
+    ]);
+    fs::write(
+        home.config.home.join("config.toml"),
+        "[tags]\nwork = [\"^example.com/work/*\"]\ntools = [\"@work\", \"tool\"]\n",
+    )
+    .unwrap();
+    let list = |args: &[&str]| {
+        let before = home.capture.lines().len();
+        let code = home.run(&[&["list"], args].concat());
+        let mut lines = home.capture.lines().split_off(before);
+        lines.sort();
+        (code, lines)
+    };
+
+    assert_eq!(
+        list(&["@work", "--exclude", "*/legacy-*"]),
+        (0, vec!["example.com/work/api".to_string()])
+    );
+    assert_eq!(
+        list(&["--exclude", "@work", "--exclude", "dots"]),
+        (0, vec!["example.com/personal/tool".to_string()])
+    );
+    assert_eq!(
+        list(&["@tools"]),
+        (
+            0,
+            vec![
+                "example.com/personal/tool".to_string(),
+                "example.com/work/api".to_string(),
+                "example.com/work/legacy-api".to_string(),
+            ]
+        )
+    );
+    assert_eq!(list(&["@missing"]), (1, vec![]));
+    assert_eq!(home.capture.infos(), vec!["unknown tag '@missing'"]);
+}
+
+#[test]
+fn execute_reports_exit_codes() {
+    let home = Home::new("execute");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+
+    assert_eq!(home.run(&["execute", "tool", "true"]), 0);
+    assert_eq!(home.run(&["execute", "tool", "false"]), 1);
+    assert_eq!(
+        home.capture.results()[1..],
+        [
+            (remote.id(), "OK".to_string()),
+            (remote.id(), "E1".to_string())
+        ]
+    );
+}
+
+#[test]
+fn list_git_status() {
+    let home = Home::new("list-git");
+    let remote = home.remote("tool");
+    home.run(&["clone", &remote.url()]);
+    std::fs::write(home.src(&remote.id()).join("README"), "changed\n").unwrap();
+
+    assert_eq!(home.run(&["list", "--git"]), 0);
+    assert_eq!(
+        home.capture.lines(),
+        vec![format!("{}:master [0:0]\n  m README", remote.id())]
+    );
+}
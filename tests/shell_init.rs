@@ -0,0 +1,96 @@
+#![cfg(target_family = "unix")]
+
+mod common;
+
+use common::Home;
+use osoy::operator::shell_init::{script, Shell};
+use std::path::Path;
+use std::process::Command;
+use std::{env, fs};
+
+/// Run command in bash after loading shell integration with `osoy` of this build on path
+/// returning its output when it succeeds.
+fn bash(home: &Home, dir: &Path, command: &str) -> Option<String> {
+    let path = home.dir.join("path");
+    if !path.exists() {
+        fs::create_dir_all(&path).unwrap();
+        std::os::unix::fs::symlink(env!("CARGO_BIN_EXE_osoy"), path.join("osoy")).unwrap();
+    }
+    let output = Command::new("bash")
+        .arg("-c")
+        .arg(format!(
+            "{}\n{}",
+            script(Shell::Bash, "oc", &home.config),
+            command
+        ))
+        .current_dir(dir)
+        .env(
+            "PATH",
+            format!("{}:{}", path.display(), env::var("PATH").unwrap()),
+        )
+        .env("OSOY_HOME", &home.config.home)
+        .output()
+        .expect("bash is required to test shell integration");
+    match output.status.success() {
+        true => Some(String::from_utf8_lossy(&output.stdout).into()),
+        false => None,
+    }
+}
+
+/// Complete the last of given words with `osoy` completion of bash.
+fn complete(home: &Home, words: &str) -> Vec<String> {
+    bash(
+        home,
+        &home.dir,
+        &format!(
+            r#"COMP_WORDS=({}); COMP_CWORD=$((${{#COMP_WORDS[@]}} - 1))
+_osoy_complete osoy; printf '%s\n' "${{COMPREPLY[@]}}""#,
+            words
+        ),
+    )
+    .unwrap()
+    .lines()
+    .map(String::from)
+    .collect()
+}
+
+#[test]
+fn bash_prompt() {
+    let home = Home::new("shell-init-prompt");
+    let repo = home.src("gitlab.com/group/sub/tool");
+    fs::create_dir_all(repo.join(".git")).unwrap();
+    fs::create_dir_all(repo.join("src/bin")).unwrap();
+
+    assert_eq!(
+        bash(&home, &repo.join("src/bin"), "osoy_prompt"),
+        Some("gitlab.com/group/sub/tool\n".into())
+    );
+    assert_eq!(bash(&home, &home.src("gitlab.com"), "osoy_prompt"), None);
+    assert_eq!(bash(&home, &home.dir, "osoy_prompt"), None);
+}
+
+#[test]
+fn bash_completion() {
+    let home = Home::new("shell-init-completion");
+    let remote = home.remote("tool");
+    assert_eq!(home.run(&["clone", &remote.url()]), 0);
+
+    assert!(complete(&home, "osoy li").contains(&"link".to_string()));
+    assert!(complete(&home, "osoy link --mo").contains(&"--mode".to_string()));
+    assert_eq!(complete(&home, "osoy link ''"), vec![remote.id()]);
+    assert_eq!(complete(&home, "osoy link -f ''"), vec![remote.id()]);
+    assert_eq!(
+        bash(&home, &home.dir, "oc tool && osoy_prompt"),
+        Some(format!("{}\n", remote.id()))
+    );
+}
+
+#[test]
+fn zsh_completion() {
+    let home = Home::new("shell-init-zsh");
+    let script = script(Shell::Zsh, "oc", &home.config);
+
+    assert!(script.contains(":_osoy_id_candidates'"));
+    assert!(script.contains("compdef _osoy_id_candidates oc"));
+    assert!(!script.lines().any(|line| line == r#"_osoy "$@""#));
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use common::Home;
+use git2::{Repository, Signature};
+use osoy::Operator;
+use std::fs;
+use structopt::StructOpt;
+
+fn setup(name: &str) -> (Home, String, String) {
+    let home = Home::new(name);
+    let clean = home.remote("clean");
+    let busy = home.remote("busy");
+    home.run(&["clone", &clean.url(), &busy.url()]);
+    (home, clean.id(), busy.id())
+}
+
+#[test]
+fn summary_table() {
+    let (home, clean, busy) = setup("status-table");
+    let local = home.src(&busy);
+    common::commit(&local, &[("LOCAL", "local\n")], "local");
+    fs::write(local.join("README"), "stashed\n").unwrap();
+    Repository::open(&local)
+        .unwrap()
+        .stash_save(&Signature::now("a", "a@b").unwrap(), "wip", None)
+        .unwrap();
+    fs::write(local.join("README"), "changed\n").unwrap();
+    fs::write(local.join("NEW"), "new\n").unwrap();
+
+    assert_eq!(home.run(&["status"]), 0);
+    let lines = home.capture.lines();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("id"));
+    let busy_cells = lines
+        .iter()
+        .find(|line| line.starts_with(&busy))
+        .unwrap()
+        .split_whitespace()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        busy_cells,
+        [&busy, "master", "1", "0", "0", "1", "1", "1", "now"]
+    );
+    let clean_cells = lines
+        .iter()
+        .find(|line| line.starts_with(&clean))
+        .unwrap()
+        .split_whitespace()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        clean_cells,
+        [&clean, "master", "0", "0", "0", "0", "0", "0", "now"]
+    );
+}
+
+#[test]
+fn filters() {
+    let (home, clean, busy) = setup("status-filters");
+    common::commit(&home.src(&busy), &[("LOCAL", "local\n")], "local");
+
+    home.run(&["status", "--ahead"]);
+    let lines = home.capture.lines();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].starts_with(&busy));
+
+    home.run(&["status", "--dirty", "--behind", "--diverged"]);
+    assert_eq!(home.capture.lines().len(), 2);
+
+    home.run(&["status", "--no-upstream"]);
+    assert_eq!(home.capture.lines().len(), 2);
+
+    home.run(&["status", &clean]);
+    assert_eq!(home.capture.lines().len(), 4);
+}
+
+#[test]
+fn list_only_details_shows_ahead() {
+    let (home, _, busy) = setup("status-list-ahead");
+    common::commit(&home.src(&busy), &[("LOCAL", "local\n")], "local");
+
+    assert_eq!(home.run(&["list", "--git", "--only-details"]), 0);
+    assert_eq!(home.capture.lines(), vec![format!("{}:master [1:0]", busy)]);
+}
+
+#[test]
+fn where_predicates() {
+    let (home, clean, busy) = setup("status-where");
+    common::commit(&home.src(&busy), &[("LOCAL", "local\n")], "local");
+    fs::write(home.src(&clean).join("README"), "changed\n").unwrap();
+    let list = |predicates: &[&str]| {
+        let before = home.capture.lines().len();
+        let mut args = vec!["list"];
+        for predicate in predicates {
+            args.extend(["--where", predicate]);
+        }
+        let code = home.run(&args);
+        (code, home.capture.lines().split_off(before))
+    };
+
+    assert_eq!(list(&["ahead"]), (0, vec![busy.clone()]));
+    assert_eq!(list(&["dirty"]), (0, vec![clean.clone()]));
+    assert_eq!(list(&["!dirty", "ahead=1"]), (0, vec![busy.clone()]));
+    assert_eq!(list(&["branch=master", "last-commit<1d", "ahead>1"]).0, 1);
+    assert_eq!(list(&["host=example.com"]).0, 1);
+    assert_eq!(list(&["branch=mast*", "!exe"]).1.len(), 2);
+    assert!(Operator::from_iter_safe(["osoy", "list", "--where", "stale"]).is_err());
+}
+
+#[test]
+fn where_reports_unreadable() {
+    let (home, clean, busy) = setup("status-where-unreadable");
+    fs::create_dir_all(home.src("example.com/broken/.git")).unwrap();
+
+    assert_eq!(home.run(&["list", "--where", "!dirty"]), 0);
+    let mut lines = home.capture.lines();
+    lines.sort();
+    assert_eq!(lines, vec![busy, clean]);
+    assert_eq!(home.capture.infos().len(), 1);
+    assert!(home.capture.infos()[0].starts_with(&format!(
+        "could not read state of '{}': ",
+        home.src("example.com/broken").display()
+    )));
+}